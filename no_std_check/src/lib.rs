@@ -0,0 +1,42 @@
+//! Builds the battleship engine without std and without an allocator. It
+//! has to be built on its own, as the game binary turns std on for the
+//! whole workspace:
+//!
+//! ```sh
+//! cargo build -p no_std_check
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+
+use battleship::ai::{play_computers, HuntStrategy, RandomStrategy};
+use battleship::log::MOVES_CAPACITY;
+use battleship::rng::Rng;
+use battleship::{Player, Rules};
+
+/// Plays a whole game between two computers. Returns the winner and the
+/// shots fired, so every part of the engine has to link.
+pub fn play(rules: Rules, seed: u64) -> (Player, usize) {
+    let mut rng = Rng::new(seed);
+    let (game, next, fired) = play_computers(
+        rules,
+        [&mut HuntStrategy, &mut RandomStrategy],
+        Player::Alpha,
+        MOVES_CAPACITY,
+        &mut rng,
+    );
+
+    (game.get_winner().unwrap_or(next), fired.iter().sum())
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn games_end() {
+        for rules in battleship::rules::RULES {
+            let (_, fired) = play(rules, 5);
+            assert!(fired <= 200, "{}", rules.name);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+//! Line oriented interface for screen readers. Everything is announced in
+//! sentences and cells are typed in the notation printed around the boards,
+//! so nothing depends on reading the grid.
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::ai::Strategy;
+use crate::board_api::board_get;
+use crate::constants::BOARD_SIZE;
+use crate::coord::Coord;
+use crate::game::{
+    ship_at, ship_title, Game, Interrupt, Player, Shot, SHIPS_COUNT, SHIP_NAMES, SHIP_SIZES,
+};
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::save;
+use crate::session::{self, Frontend};
+use crate::view::PlayerView;
+
+const FLEET_HELP: &str = "Type a cell and a direction to place the ship, like B2 across or \
+B2 down. Other commands: random, undo, board, save, concede, quit.";
+
+const SHOOT_HELP: &str = "Type a cell to fire at, like C5. Other commands: board lists \
+your fleet, targets lists your shots, ships tells which ships are sunk, save, concede, \
+quit.";
+
+pub struct LineIO<R, W> {
+    input: R,
+    output: W,
+    pub rng: Rng,
+}
+
+impl<R: BufRead, W: Write> LineIO<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        LineIO {
+            input,
+            output,
+            rng: Rng::default(),
+        }
+    }
+
+    pub fn say(&mut self, text: &str) {
+        writeln!(self.output, "{}", text).unwrap();
+        self.output.flush().unwrap();
+    }
+
+    /// Reads one line, trimmed and in lower case. The end of input quits.
+    fn read_line(&mut self, prompt: &str) -> Result<String, Interrupt> {
+        write!(self.output, "{} ", prompt).unwrap();
+        self.output.flush().unwrap();
+
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => Err(Interrupt::Quit),
+            Ok(_) => Ok(line.trim().to_lowercase()),
+        }
+    }
+
+    pub fn ask(&mut self, question: &str) -> Result<bool, Interrupt> {
+        loop {
+            match self
+                .read_line(&format!("{} Type yes or no.", question))?
+                .as_str()
+            {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Commands available both while placing and while shooting. Returns
+    /// `Ok(true)` when the command was handled.
+    fn common_command(
+        &mut self,
+        command: &str,
+        view: &PlayerView,
+        save: &dyn Fn() -> io::Result<PathBuf>,
+    ) -> Result<bool, Interrupt> {
+        match command {
+            "board" | "fleet" => {
+                for line in describe_fleet(view) {
+                    self.say(&line);
+                }
+            }
+            "save" => {
+                let text = match save() {
+                    Ok(path) => format!("Game saved to {}.", path.display()),
+                    Err(err) => format!("Cannot save the game: {}.", err),
+                };
+                self.say(&text);
+            }
+            "concede" if self.ask("Concede the game?")? => return Err(Interrupt::Concede),
+            "quit" if self.ask("Quit without saving?")? => return Err(Interrupt::Quit),
+            "concede" | "quit" => {}
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Lets the player place the whole fleet, starting from the ships
+    /// already placed, and returns it. `save` saves the game with the
+    /// fleet as far as it is placed.
+    pub fn read_fleet(
+        &mut self,
+        rules: Rules,
+        ships: [u128; SHIPS_COUNT],
+        save: &dyn Fn(&[u128; SHIPS_COUNT]) -> io::Result<PathBuf>,
+    ) -> Result<[u128; SHIPS_COUNT], Interrupt> {
+        // Only the fleet being placed, so the rules check every ship
+        let player = Player::Alpha;
+        let mut game = Game {
+            rules,
+            ..Game::default()
+        };
+        // A layout that breaks the rules starts over from an empty board
+        let _ = game.place_partial_fleet(player, ships);
+        self.say(FLEET_HELP);
+
+        loop {
+            let ships = game.get_ships(player);
+            let placed = ships.iter().position(|&ship| ship == 0);
+
+            let prompt = match placed {
+                Some(layer) => format!(
+                    "Place your {}, {} cells.",
+                    SHIP_NAMES[layer], SHIP_SIZES[layer]
+                ),
+                None => String::from("Fleet is ready. Type done to confirm, or undo."),
+            };
+            let line = self.read_line(&prompt)?;
+
+            let view = PlayerView::new(&game, player);
+            if self.common_command(&line, &view, &|| save(&ships))? {
+                continue;
+            }
+
+            match (line.as_str(), placed) {
+                ("help", _) => self.say(FLEET_HELP),
+                ("done", None) => return Ok(ships),
+                ("random", _) => {
+                    game.place_random_fleet(player, &mut self.rng);
+                    self.say("Ships placed at random.");
+                }
+                ("undo", _) => {
+                    let last = match placed {
+                        Some(layer) => layer.checked_sub(1),
+                        None => Some(SHIP_SIZES.len() - 1),
+                    };
+                    match last {
+                        Some(layer) => {
+                            game.remove_ship(player, layer);
+                            self.say(&format!("Took back your {}.", SHIP_NAMES[layer]));
+                        }
+                        None => self.say("No ship to take back."),
+                    }
+                }
+                (_, Some(layer)) => match parse_placement(&line, SHIP_SIZES[layer]) {
+                    Ok(ship) => match game.add_ship(player, ship, layer) {
+                        Ok(()) => self.say(&format!(
+                            "{} placed {}.",
+                            ship_title(layer),
+                            describe_cells(ship)
+                        )),
+                        Err(err) => {
+                            let mut message = format!("Cannot place it there: {}.", err);
+                            if !rules.touching {
+                                message.push_str(
+                                    " Ships cannot touch each other, not even at corners.",
+                                );
+                            }
+                            self.say(&message);
+                        }
+                    },
+                    Err(message) => self.say(message),
+                },
+                (_, None) => self.say("All ships are placed. Type done or undo."),
+            }
+        }
+    }
+
+    /// Asks for a cell to fire at. The game in play is saved through
+    /// `save`.
+    pub fn read_shoot(
+        &mut self,
+        view: &PlayerView,
+        save: &dyn Fn() -> io::Result<PathBuf>,
+    ) -> Result<u128, Interrupt> {
+        loop {
+            let line = self.read_line("Your shot?")?;
+
+            if self.common_command(&line, view, save)? {
+                continue;
+            }
+
+            match line.as_str() {
+                "help" => self.say(SHOOT_HELP),
+                "targets" => {
+                    for line in describe_targets(view) {
+                        self.say(&line);
+                    }
+                }
+                "ships" => {
+                    for line in describe_ships(view) {
+                        self.say(&line);
+                    }
+                }
+                _ => match line.parse::<Coord>() {
+                    Ok(coord) if view.shots & coord.to_board() != 0 => {
+                        self.say(&format!("{} is already known. Pick another cell.", coord));
+                    }
+                    Ok(coord) => return Ok(coord.to_board()),
+                    Err(err) => self.say(&format!("{}. Type help for commands.", err)),
+                },
+            }
+        }
+    }
+}
+
+/// Plays the game from the given state, announcing every shot to both
+/// players. With a computer it plays Beta. Only quitting is returned as an
+/// error.
+pub fn play<'a, R: BufRead, W: Write>(
+    game: &mut Game,
+    io: &'a mut LineIO<R, W>,
+    turn: Player,
+    computer: Option<&'a mut dyn Strategy>,
+) -> Result<(), Interrupt> {
+    let mut frontend = Lines {
+        io,
+        computer,
+        first: turn,
+        unheard: [Vec::new(), Vec::new()],
+    };
+    session::play(game, turn, &mut frontend)
+}
+
+/// Both players at the same screen reader, or one against the computer.
+struct Lines<'a, R, W> {
+    io: &'a mut LineIO<R, W>,
+    computer: Option<&'a mut dyn Strategy>,
+    /// Who starts, kept by saves made before the first shot
+    first: Player,
+    /// Shots each player has not heard about yet, by player
+    unheard: [Vec<String>; 2],
+}
+
+impl<R: BufRead, W: Write> Lines<'_, R, W> {
+    fn is_computer(&self, player: Player) -> bool {
+        player == Player::Beta && self.computer.is_some()
+    }
+}
+
+impl<R: BufRead, W: Write> Frontend for Lines<'_, R, W> {
+    fn place_fleet(&mut self, game: &mut Game, player: Player) -> Result<(), Interrupt> {
+        if self.is_computer(player) {
+            game.place_random_fleet(player, &mut self.io.rng);
+            self.io.say("The computer placed its ships.");
+            return Ok(());
+        }
+
+        self.io
+            .say(&format!("Player {}, place your ships.", player));
+        let save = |ships: &_| save::save_placing(game, player, ships, self.first);
+        let ships = self
+            .io
+            .read_fleet(game.rules, game.get_ships(player), &save)?;
+        game.place_fleet(player, ships)
+            .expect("placed by the rules");
+        Ok(())
+    }
+
+    fn start(&mut self, _game: &Game) {
+        self.io
+            .say("Game starts! Type help at any prompt for commands.");
+    }
+
+    fn start_turn(&mut self, _game: &Game, player: Player, shots: usize) -> Result<(), Interrupt> {
+        let unheard = std::mem::take(&mut self.unheard[player.index()]);
+        if self.is_computer(player) {
+            return Ok(());
+        }
+
+        self.io.say(&format!("Player {}, your turn.", player));
+        for text in unheard {
+            self.io.say(&format!("Enemy {}", text));
+        }
+        if shots > 1 {
+            self.io.say(&format!("You have {} shots this turn.", shots));
+        }
+        Ok(())
+    }
+
+    fn next_shot(&mut self, game: &Game, player: Player) -> Result<u128, Interrupt> {
+        let view = PlayerView::new(game, player);
+        match self.computer.as_deref_mut() {
+            Some(strategy) if player == Player::Beta => {
+                Ok(strategy.next_shot(&view, &mut self.io.rng))
+            }
+            _ => self.io.read_shoot(&view, &|| save::save_game(game, player)),
+        }
+    }
+
+    fn fired(
+        &mut self,
+        _game: &Game,
+        player: Player,
+        shoot: u128,
+        shot: Shot,
+    ) -> Result<(), Interrupt> {
+        let text = describe_shot(shoot, shot);
+        if !self.is_computer(player) {
+            self.io.say(&format!("Your {}", text));
+        }
+        self.unheard[player.other().index()].push(text);
+        Ok(())
+    }
+}
+
+pub fn describe_result(game: &Game) -> String {
+    match (game.conceded, game.get_winner()) {
+        (Some(loser), Some(winner)) => {
+            format!("Player {} conceded. Player {} wins.", loser, winner)
+        }
+        (None, Some(winner)) => format!("Player {} sank the whole fleet and wins.", winner),
+        _ => String::from("The game is not over."),
+    }
+}
+
+/// `shot at C5: hit.`, to be prefixed with whose shot it was.
+pub fn describe_shot(shoot: u128, shot: Shot) -> String {
+    let coord = Coord::from_board(shoot).expect("a shot hits one cell");
+    match shot {
+        Shot::Miss => format!("shot at {}: miss.", coord),
+        Shot::Hit => format!("shot at {}: hit.", coord),
+        Shot::Sunk(layer) => format!("shot at {}: hit, {} sunk.", coord, SHIP_NAMES[layer]),
+    }
+}
+
+/// The player's own board, one sentence per row.
+pub fn describe_fleet(view: &PlayerView) -> Vec<String> {
+    let board = view.board();
+    let shoots = view.incoming;
+
+    describe_rows(
+        |x, y| match (board_get(board, x, y), board_get(shoots, x, y)) {
+            (true, true) => "hit ship",
+            (true, false) => "ship",
+            (false, true) => "miss",
+            (false, false) => "water",
+        },
+    )
+}
+
+/// What the player knows about the enemy board, one sentence per row.
+pub fn describe_targets(view: &PlayerView) -> Vec<String> {
+    describe_rows(
+        |x, y| match (board_get(view.shots, x, y), board_get(view.hits, x, y)) {
+            (true, true) => "hit",
+            (true, false) => "miss",
+            (false, _) => "unknown",
+        },
+    )
+}
+
+/// State of both fleets as far as the player may know it: damage of their
+/// own ships, but only which enemy ships are sunk.
+pub fn describe_ships(view: &PlayerView) -> Vec<String> {
+    let mut lines: Vec<String> = view
+        .own_fleet()
+        .into_iter()
+        .enumerate()
+        .map(|(layer, ship)| match (ship.sunk, ship.damage) {
+            (true, _) => format!("Your {}: sunk.", SHIP_NAMES[layer]),
+            (false, 0) => format!("Your {}: undamaged.", SHIP_NAMES[layer]),
+            (false, hits) => format!(
+                "Your {}: {} of {} cells hit.",
+                SHIP_NAMES[layer], hits, SHIP_SIZES[layer]
+            ),
+        })
+        .collect();
+
+    lines.extend((0..SHIPS_COUNT).map(|layer| {
+        let state = match view.is_enemy_sunk(layer) {
+            true => "sunk",
+            false => "afloat",
+        };
+        format!("Enemy {}: {}.", SHIP_NAMES[layer], state)
+    }));
+
+    lines
+}
+
+/// Reads rows as runs of equal cells, like `Row 3: A to C water, D ship,
+/// E to J water.`, which is much shorter to listen to than ten cells.
+fn describe_rows(cell: impl Fn(usize, usize) -> &'static str) -> Vec<String> {
+    (0..BOARD_SIZE)
+        .map(|y| {
+            let mut runs: Vec<(usize, usize, &str)> = Vec::new();
+            for x in 0..BOARD_SIZE {
+                match runs.last_mut() {
+                    Some((_, end, name)) if *name == cell(x, y) => *end = x,
+                    _ => runs.push((x, x, cell(x, y))),
+                }
+            }
+
+            let runs: Vec<String> = runs
+                .into_iter()
+                .map(|(start, end, name)| match (start, end) {
+                    _ if start == 0 && end == BOARD_SIZE - 1 => format!("all {}", name),
+                    _ if start == end => format!("{} {}", Coord::column_name(start), name),
+                    _ => format!(
+                        "{} to {} {}",
+                        Coord::column_name(start),
+                        Coord::column_name(end),
+                        name
+                    ),
+                })
+                .collect();
+
+            format!("Row {}: {}.", y + 1, runs.join(", "))
+        })
+        .collect()
+}
+
+/// `from B2 to B4`, or `at B2` for a single cell.
+fn describe_cells(ship: u128) -> String {
+    let cells: Vec<Coord> = Coord::all()
+        .filter(|coord| board_get(ship, coord.x, coord.y))
+        .collect();
+
+    match cells.as_slice() {
+        [cell] => format!("at {}", cell),
+        [first, .., last] => format!("from {} to {}", first, last),
+        [] => String::from("nowhere"),
+    }
+}
+
+/// Parses `B2 across` or `B2 down` into a ship of the given size that
+/// starts at the cell.
+fn parse_placement(line: &str, size: usize) -> Result<u128, &'static str> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (cell, vertical) = match words.as_slice() {
+        [cell] | [cell, "across" | "a" | "right" | "r"] => (cell, false),
+        [cell, "down" | "d"] => (cell, true),
+        _ => return Err("Type a cell and a direction, like B2 across or B2 down."),
+    };
+    let coord: Coord = cell
+        .parse()
+        .map_err(|_| "Cells are a column A to J and a row 1 to 10, like B2.")?;
+
+    ship_at(coord, size, vertical).ok_or("The ship does not fit on the board there.")
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn placement_commands() {
+        assert_eq!(
+            parse_placement("a1 across", 3),
+            Ok(crate::board_api::create_ship(3))
+        );
+        assert_eq!(
+            describe_cells(parse_placement("b2 down", 3).unwrap()),
+            "from B2 to B4"
+        );
+        assert!(parse_placement("h1", 4).is_err());
+        assert!(parse_placement("a8 down", 4).is_err());
+        assert!(parse_placement("a1 sideways", 2).is_err());
+    }
+
+    #[test]
+    fn rows_are_read_as_runs() {
+        let mut game = Game::default();
+        let ship = parse_placement("b3 across", 3).unwrap();
+        game.add_ship(Player::Alpha, ship, 2).unwrap();
+        game.shoot(Player::Beta, Coord::new(2, 2).to_board())
+            .unwrap();
+
+        let rows = describe_fleet(&PlayerView::new(&game, Player::Alpha));
+        assert_eq!(rows[0], "Row 1: all water.");
+        assert_eq!(
+            rows[2],
+            "Row 3: A water, B ship, C hit ship, D ship, E to J water."
+        );
+
+        let rows = describe_targets(&PlayerView::new(&game, Player::Beta));
+        assert_eq!(rows[2], "Row 3: A to B unknown, C hit, D to J unknown.");
+    }
+
+    #[test]
+    fn placement_errors_follow_the_rules() {
+        for (rules, touching) in [(Rules::CLASSIC, true), (Rules::RELAXED, false)] {
+            let mut output = Vec::new();
+            let mut io = LineIO::new("a1\na1\nquit\nyes\n".as_bytes(), &mut output);
+            let saved = io.read_fleet(rules, [0; SHIPS_COUNT], &|_| unreachable!());
+            assert_eq!(saved, Err(Interrupt::Quit));
+
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.contains("Cannot place it there: another ship is in the way."));
+            assert_eq!(output.contains("Ships cannot touch each other"), touching);
+        }
+    }
+
+    #[test]
+    fn scripted_game() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Beta, &mut Rng::new(5));
+        let target = Coord::from_board(game.get_board(Player::Beta)).unwrap();
+
+        let input = format!(
+            "a1\nc1 down\nz9\nrandom\ndone\n{}\nboard\nconcede\nyes\n",
+            target
+        );
+        let mut output = Vec::new();
+        let mut io = LineIO::new(input.as_bytes(), &mut output);
+        io.rng = Rng::new(1);
+
+        assert_eq!(play(&mut game, &mut io, Player::Alpha, None), Ok(()));
+        assert_eq!(game.get_winner(), Some(Player::Alpha));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Carrier placed from A1 to E1."));
+        assert!(output.contains("Ships cannot touch each other"));
+        assert!(output.contains(&format!("Your shot at {}: hit", target)));
+        assert!(output.contains(&format!("Enemy shot at {}", target)));
+        assert!(output.contains("Row 10:"));
+    }
+
+    #[test]
+    fn ships_hide_enemy_damage() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(2));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(3));
+        let carrier = Coord::from_board(game.get_ships(Player::Beta)[0]).unwrap();
+        game.shoot(Player::Alpha, carrier.to_board()).unwrap();
+
+        let beta = describe_ships(&PlayerView::new(&game, Player::Beta));
+        assert_eq!(beta[0], "Your carrier: 1 of 5 cells hit.");
+        assert_eq!(beta[1], "Your battleship: undamaged.");
+
+        let alpha = describe_ships(&PlayerView::new(&game, Player::Alpha));
+        assert_eq!(alpha[5], "Enemy carrier: afloat.");
+        assert!(alpha.iter().all(|line| !line.contains("hit")));
+    }
+
+    #[test]
+    fn computer_opponent() {
+        let mut game = Game {
+            rules: crate::rules::Rules::SALVO,
+            ..Game::default()
+        };
+        let input = "random\ndone\na1\nb1\nc1\nd1\ne1\nconcede\nyes\n";
+        let mut output = Vec::new();
+        let mut io = LineIO::new(input.as_bytes(), &mut output);
+        io.rng = Rng::new(2);
+
+        let mut computer = crate::ai::HuntStrategy;
+        assert_eq!(
+            play(&mut game, &mut io, Player::Alpha, Some(&mut computer)),
+            Ok(())
+        );
+        assert_eq!(game.conceded, Some(Player::Alpha));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("The computer placed its ships."));
+        assert!(output.contains("You have 5 shots this turn."));
+        assert_eq!(output.matches("Enemy shot at").count(), 5);
+    }
+}
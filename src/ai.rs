@@ -0,0 +1,246 @@
+use crate::board_api::{cutting_move, Direction};
+use crate::constants::CELL;
+use crate::coord::Coord;
+use crate::game::{Game, Player};
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::view::PlayerView;
+
+/// A computer player. Strategies only get the player's view, so they
+/// cannot peek at the enemy fleet.
+pub trait Strategy {
+    fn name(&self) -> &'static str;
+
+    /// Picks a cell of the enemy board that was not shot at yet.
+    fn next_shot(&mut self, view: &PlayerView, rng: &mut Rng) -> u128;
+}
+
+pub const STRATEGY_NAMES: [&str; 2] = ["random", "hunt"];
+
+#[cfg(feature = "std")]
+pub fn strategy_by_name(name: &str) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy)),
+        "hunt" => Some(Box::new(HuntStrategy)),
+        _ => None,
+    }
+}
+
+/// Fires at any unknown cell.
+pub struct RandomStrategy;
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn next_shot(&mut self, view: &PlayerView, rng: &mut Rng) -> u128 {
+        random_cell(view.unknown(), rng)
+    }
+}
+
+/// Searches on a checkerboard, since every ship covers at least one of its
+/// cells, and finishes off a ship once it is hit.
+pub struct HuntStrategy;
+
+impl Strategy for HuntStrategy {
+    fn name(&self) -> &'static str {
+        "hunt"
+    }
+
+    fn next_shot(&mut self, view: &PlayerView, rng: &mut Rng) -> u128 {
+        let unknown = view.unknown();
+        let open = view.open_hits();
+
+        if open != 0 {
+            use Direction::*;
+            let horizontal = cutting_move(open, Left) | cutting_move(open, Right);
+            let vertical = cutting_move(open, Up) | cutting_move(open, Down);
+
+            // Two hits next to each other give away which way the ship lies
+            let preferred = match (open & horizontal != 0, open & vertical != 0) {
+                (true, false) => horizontal,
+                (false, true) => vertical,
+                _ => horizontal | vertical,
+            };
+
+            for candidates in [preferred & unknown, (horizontal | vertical) & unknown] {
+                if candidates != 0 {
+                    return random_cell(candidates, rng);
+                }
+            }
+        }
+
+        match unknown & checkerboard() {
+            0 => random_cell(unknown, rng),
+            candidates => random_cell(candidates, rng),
+        }
+    }
+}
+
+/// Fires at the first unknown cell in reading order. Not much of a player,
+/// but its games are easy to follow, which suits tests.
+pub struct InOrderStrategy;
+
+impl Strategy for InOrderStrategy {
+    fn name(&self) -> &'static str {
+        "in-order"
+    }
+
+    fn next_shot(&mut self, view: &PlayerView, _rng: &mut Rng) -> u128 {
+        CELL >> view.unknown().leading_zeros()
+    }
+}
+
+/// Plays a game between two strategies, Alpha's first, on random fleets.
+/// Players take turns of `shots_per_turn` shots, starting with `first`,
+/// until the game is over or `max_shots` shots were fired. Returns the
+/// game, who shoots next and how many shots each player fired.
+pub fn play_computers(
+    rules: Rules,
+    strategies: [&mut dyn Strategy; 2],
+    first: Player,
+    max_shots: usize,
+    rng: &mut Rng,
+) -> (Game, Player, [usize; 2]) {
+    let mut game = Game {
+        rules,
+        ..Game::default()
+    };
+    game.place_random_fleet(Player::Alpha, rng);
+    game.place_random_fleet(Player::Beta, rng);
+
+    let mut player = first;
+    let mut fired = [0; 2];
+    while !game.is_over() {
+        for _ in 0..game.shots_per_turn(player) {
+            if game.is_over() {
+                break;
+            }
+            if fired.iter().sum::<usize>() == max_shots {
+                return (game, player, fired);
+            }
+            let view = PlayerView::new(&game, player);
+            let shoot = strategies[player.index()].next_shot(&view, rng);
+            game.shoot(player, shoot)
+                .expect("strategies fire at unknown cells");
+            fired[player.index()] += 1;
+        }
+        player = player.other();
+    }
+
+    (game, player, fired)
+}
+
+/// A game of two `InOrderStrategy` players on random fleets from `seed`,
+/// Alpha first, stopped after `shots` shots. Tests that need a game in
+/// progress, or a finished one with `MOVES_CAPACITY` shots, start here.
+#[cfg(test)]
+pub fn played(rules: Rules, seed: u64, shots: usize) -> Game {
+    play_computers(
+        rules,
+        [&mut InOrderStrategy, &mut InOrderStrategy],
+        Player::Alpha,
+        shots,
+        &mut Rng::new(seed),
+    )
+    .0
+}
+
+/// Cells where the column and row add up to an even number.
+fn checkerboard() -> u128 {
+    Coord::all()
+        .filter(|coord| (coord.x + coord.y) % 2 == 0)
+        .fold(0, |board, coord| board | coord.to_board())
+}
+
+/// One cell of the board, picked uniformly.
+pub fn random_cell(board: u128, rng: &mut Rng) -> u128 {
+    debug_assert!(board != 0, "no cell to pick from");
+
+    let mut rest = board;
+    for _ in 0..rng.below(board.count_ones() as usize) {
+        rest &= !(CELL >> rest.leading_zeros());
+    }
+    CELL >> rest.leading_zeros()
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn play_out(strategy: &mut dyn Strategy, seed: u64) -> usize {
+        let mut rng = Rng::new(seed);
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut rng);
+        game.place_random_fleet(Player::Beta, &mut rng);
+
+        let mut shots = 0;
+        while game.get_winner().is_none() {
+            let view = PlayerView::new(&game, Player::Alpha);
+            let shot = strategy.next_shot(&view, &mut rng);
+            assert_eq!(shot.count_ones(), 1);
+            assert_eq!(shot & view.shots, 0, "{} fired twice", strategy.name());
+            game.shoot(Player::Alpha, shot).unwrap();
+            shots += 1;
+        }
+        shots
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn strategies_finish_the_game() {
+        for name in STRATEGY_NAMES {
+            let mut strategy = strategy_by_name(name).unwrap();
+            assert!(play_out(strategy.as_mut(), 11) <= 100);
+        }
+        assert!(strategy_by_name("psychic").is_none());
+    }
+
+    #[test]
+    fn hunt_beats_random() {
+        let total = |strategy: &mut dyn Strategy| -> usize {
+            (0..20).map(|seed| play_out(strategy, seed)).sum()
+        };
+        assert!(total(&mut HuntStrategy) < total(&mut RandomStrategy));
+    }
+
+    #[test]
+    fn computers_take_turns() {
+        let mut rng = Rng::new(3);
+        let (game, next, fired) = play_computers(
+            Rules::SALVO,
+            [&mut HuntStrategy, &mut RandomStrategy],
+            Player::Beta,
+            7,
+            &mut rng,
+        );
+        // A whole salvo of Beta, then two shots of Alpha's
+        assert_eq!((next, fired), (Player::Alpha, [2, 5]));
+        assert_eq!(game.moves.get(0).unwrap().player, Player::Beta);
+
+        let (game, _, fired) = play_computers(
+            Rules::CLASSIC,
+            [&mut InOrderStrategy, &mut HuntStrategy],
+            Player::Alpha,
+            crate::log::MOVES_CAPACITY,
+            &mut rng,
+        );
+        assert!(game.is_over());
+        assert_eq!(
+            fired[0],
+            fired[1] + (game.get_winner() == Some(Player::Alpha)) as usize
+        );
+        assert_eq!(game.moves.get(0).unwrap().cell, Coord::new(0, 0));
+    }
+
+    #[test]
+    fn random_cell_stays_on_board() {
+        let mut rng = Rng::new(1);
+        let board = Coord::new(3, 4).to_board() | Coord::new(9, 9).to_board();
+        for _ in 0..50 {
+            assert_eq!(random_cell(board, &mut rng) & !board, 0);
+        }
+    }
+}
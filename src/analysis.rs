@@ -0,0 +1,426 @@
+//! Reviews of finished games: how each shot compares with the best shot
+//! the player had, how lucky each player was and where the lead changed
+//! hands.
+//!
+//! The chance of a hit is estimated from enemy fleets drawn at random
+//! that fit everything the shooter knows: no ship on a miss, every open
+//! hit covered and the sunk ships where they sank. A cell holds a ship in
+//! some share of those fleets, and that share is its chance.
+
+use crate::board_api::create_surround_mask;
+use crate::constants::{BOARD_COUNT, CELL};
+use crate::coord::Coord;
+use crate::game::{ship_at, SHIPS_COUNT, SHIP_SIZES};
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::view::PlayerView;
+
+#[cfg(feature = "std")]
+use crate::accessible::describe_shot;
+#[cfg(feature = "std")]
+use crate::game::{Game, Player, Shot, PLAYERS};
+
+/// Fleets drawn for every estimate.
+pub const SAMPLES: usize = 200;
+/// Most placements of one ship, across and down.
+const MAX_PLACEMENTS: usize = 2 * BOARD_COUNT;
+
+/// Estimated chance of a hit on every enemy cell, by [`Coord::index`],
+/// for the player's next shot. Cells shot already have no chance.
+pub fn hit_chances(view: &PlayerView, rules: Rules, rng: &mut Rng) -> [f64; BOARD_COUNT] {
+    let placements = Placements::new(view, rules);
+    let unknown = view.unknown();
+    let mut counts = [0; BOARD_COUNT];
+    let mut drawn = 0;
+
+    // A draw can paint itself into a corner, so a few more are tried
+    for _ in 0..4 * SAMPLES {
+        if drawn == SAMPLES {
+            break;
+        }
+        if let Some(fleet) = placements.draw(rng) {
+            for index in cells(fleet & unknown) {
+                counts[index] += 1;
+            }
+            drawn += 1;
+        }
+    }
+
+    match drawn {
+        0 => [0.0; BOARD_COUNT],
+        drawn => counts.map(|count| f64::from(count) / drawn as f64),
+    }
+}
+
+/// Where each ship afloat could be, judging by the shots alone.
+struct Placements {
+    /// Placements of every ship and how many there are.
+    ships: [([u128; MAX_PLACEMENTS], usize); SHIPS_COUNT],
+    afloat: [bool; SHIPS_COUNT],
+    open: u128,
+    sunk: u128,
+    touching: bool,
+}
+
+impl Placements {
+    fn new(view: &PlayerView, rules: Rules) -> Placements {
+        let sunk = view.sunk_enemy.iter().fold(0, |board, ship| board | ship);
+        let misses = view.shots & !view.hits;
+        let mut ships = [([0; MAX_PLACEMENTS], 0); SHIPS_COUNT];
+
+        for layer in (0..SHIPS_COUNT).filter(|&layer| !view.is_enemy_sunk(layer)) {
+            let (placements, len) = &mut ships[layer];
+            for start in Coord::all() {
+                for down in [false, true] {
+                    let Some(ship) = ship_at(start, SHIP_SIZES[layer], down) else {
+                        continue;
+                    };
+                    // A ship on hits alone would have been sunk already
+                    if ship & misses != 0 || ship & !view.hits == 0 {
+                        continue;
+                    }
+                    placements[*len] = ship;
+                    *len += 1;
+                }
+            }
+        }
+
+        Placements {
+            ships,
+            afloat: core::array::from_fn(|layer| !view.is_enemy_sunk(layer)),
+            open: view.open_hits(),
+            sunk,
+            touching: rules.touching,
+        }
+    }
+
+    /// One fleet of the ships afloat that fits the shots. Open hits are
+    /// covered first, by any ship that fits over them, and then the other
+    /// ships go wherever they fit.
+    fn draw(&self, rng: &mut Rng) -> Option<u128> {
+        let mut fleet = 0;
+        let mut placed = self.afloat.map(|afloat| !afloat);
+
+        while let Some(next) = placed.iter().position(|&placed| !placed) {
+            let taken = match self.touching {
+                true => self.sunk | fleet,
+                false => create_surround_mask(self.sunk | fleet),
+            };
+            let target = match self.open & !fleet {
+                0 => None,
+                uncovered => Some(CELL >> uncovered.leading_zeros()),
+            };
+
+            let candidates = || {
+                (0..SHIPS_COUNT)
+                    .filter(|&layer| !placed[layer] && (target.is_some() || layer == next))
+                    .flat_map(|layer| {
+                        let (placements, len) = &self.ships[layer];
+                        placements[..*len].iter().map(move |&ship| (layer, ship))
+                    })
+                    .filter(|&(_, ship)| {
+                        ship & taken == 0 && target.is_none_or(|target| ship & target != 0)
+                    })
+            };
+            let count = candidates().count();
+            if count == 0 {
+                return None;
+            }
+            let (layer, ship) = candidates().nth(rng.below(count))?;
+            fleet |= ship;
+            placed[layer] = true;
+        }
+
+        (self.open & !fleet == 0).then_some(fleet)
+    }
+}
+
+/// The unknown cell with the best chance of a hit and the chance, the
+/// first in reading order on a tie.
+pub fn best_shot(chances: &[f64; BOARD_COUNT], view: &PlayerView) -> Option<(Coord, f64)> {
+    let mut best: Option<(usize, f64)> = None;
+    for index in cells(view.unknown()) {
+        if best.is_none_or(|(_, chance)| chances[index] > chance) {
+            best = Some((index, chances[index]));
+        }
+    }
+    let (index, chance) = best?;
+    Some((Coord::from_index(index)?, chance))
+}
+
+/// Indexes of the cells of the board. The top bit is `A1`, so the index
+/// is the number of bits above the cell.
+fn cells(board: u128) -> impl Iterator<Item = usize> {
+    let mut rest = board;
+    core::iter::from_fn(move || {
+        let index = rest.leading_zeros() as usize;
+        if index >= BOARD_COUNT {
+            return None;
+        }
+        rest &= !(CELL >> index);
+        Some(index)
+    })
+}
+
+/// One shot of a game next to the best one the player had.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShotReview {
+    pub player: Player,
+    pub cell: Coord,
+    pub shot: Shot,
+    pub chance: f64,
+    pub best: Coord,
+    pub best_chance: f64,
+}
+
+#[cfg(feature = "std")]
+impl ShotReview {
+    /// Chance of the shot compared to the best one, 1 for the best shot.
+    pub fn quality(&self) -> f64 {
+        match self.best_chance > 0.0 {
+            true => self.chance / self.best_chance,
+            false => 1.0,
+        }
+    }
+
+    pub fn is_hit(&self) -> bool {
+        self.shot != Shot::Miss
+    }
+}
+
+/// A finished game shot by shot.
+#[cfg(feature = "std")]
+pub struct Review {
+    /// In the order they were fired.
+    pub shots: Vec<ShotReview>,
+    /// Shots after which the other player led, by position in `shots`.
+    /// The player with more hits leads.
+    pub turning_points: Vec<usize>,
+}
+
+#[cfg(feature = "std")]
+impl Review {
+    pub fn new(game: &Game) -> Review {
+        // The game before each shot
+        let mut position = game.without_shots();
+        // The same game always gets the same review
+        let mut rng = Rng::new(0);
+        let mut shots = Vec::new();
+        let mut turning_points = Vec::new();
+        let mut hits = [0; 2];
+        let mut leader = None;
+
+        for (i, (mv, shot, after)) in game.replay().enumerate() {
+            let view = PlayerView::new(&position, mv.player);
+            let chances = hit_chances(&view, game.rules, &mut rng);
+            let (best, best_chance) =
+                best_shot(&chances, &view).expect("the cell shot was unknown");
+            position = after;
+            shots.push(ShotReview {
+                player: mv.player,
+                cell: mv.cell,
+                shot,
+                chance: chances[mv.cell.index()],
+                best,
+                best_chance,
+            });
+
+            if shot != Shot::Miss {
+                hits[mv.player.index()] += 1;
+                let ahead = match hits[0].cmp(&hits[1]) {
+                    core::cmp::Ordering::Greater => Some(Player::Alpha),
+                    core::cmp::Ordering::Less => Some(Player::Beta),
+                    core::cmp::Ordering::Equal => None,
+                };
+                if ahead.is_some() && ahead != leader {
+                    if leader.is_some() {
+                        turning_points.push(i);
+                    }
+                    leader = ahead;
+                }
+            }
+        }
+
+        Review {
+            shots,
+            turning_points,
+        }
+    }
+
+    fn by(&self, player: Player) -> impl Iterator<Item = &ShotReview> {
+        self.shots
+            .iter()
+            .filter(move |review| review.player == player)
+    }
+
+    /// Hits the player could expect from the shots they chose.
+    pub fn expected_hits(&self, player: Player) -> f64 {
+        self.by(player).map(|review| review.chance).sum()
+    }
+
+    pub fn hits(&self, player: Player) -> usize {
+        self.by(player).filter(|review| review.is_hit()).count()
+    }
+
+    /// Hits beyond the expected ones, below zero for bad luck.
+    pub fn luck(&self, player: Player) -> f64 {
+        self.hits(player) as f64 - self.expected_hits(player)
+    }
+
+    /// Average [`ShotReview::quality`] of the player's shots.
+    pub fn average_quality(&self, player: Player) -> Option<f64> {
+        let shots = self.by(player).count();
+        let total: f64 = self.by(player).map(ShotReview::quality).sum();
+        (shots > 0).then(|| total / shots as f64)
+    }
+
+    /// `Shot 12, Player Alpha shot at A1: miss. 3% chance, B4 had 40%.`
+    pub fn describe_shot(&self, i: usize) -> String {
+        let review = &self.shots[i];
+        let chance = match review.cell == review.best {
+            true => format!("{}, the best chance.", percent(review.chance)),
+            false => format!(
+                "{} chance, {} had {}.",
+                percent(review.chance),
+                review.best,
+                percent(review.best_chance)
+            ),
+        };
+        format!(
+            "Shot {}, Player {} {} {}",
+            i + 1,
+            review.player,
+            describe_shot(review.cell.to_board(), review.shot),
+            chance
+        )
+    }
+
+    /// A few lines about the whole game: luck and shot quality of both
+    /// players, the turning points and the weakest shots.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for player in PLAYERS {
+            let quality = self
+                .average_quality(player)
+                .map_or(String::from("-"), percent);
+            lines.push(format!(
+                "Player {}: {} hits from {} shots, {:.1} expected, luck {:+.1}. \
+                 Shots had {} of the best chance on average.",
+                player,
+                self.hits(player),
+                self.by(player).count(),
+                self.expected_hits(player),
+                self.luck(player),
+                quality
+            ));
+        }
+
+        for &i in &self.turning_points {
+            let review = &self.shots[i];
+            lines.push(format!(
+                "Turning point: shot {}, Player {} takes the lead with a hit at {}.",
+                i + 1,
+                review.player,
+                review.cell
+            ));
+        }
+
+        let mut weakest: Vec<usize> = (0..self.shots.len())
+            .filter(|&i| self.shots[i].quality() < 0.5)
+            .collect();
+        weakest.sort_by(|&a, &b| self.shots[a].quality().total_cmp(&self.shots[b].quality()));
+        for i in weakest.into_iter().take(3) {
+            lines.push(format!("Weak shot: {}", self.describe_shot(i)));
+        }
+
+        lines
+    }
+}
+
+#[cfg(feature = "std")]
+fn percent(fraction: f64) -> String {
+    format!("{:.0}%", fraction * 100.0)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::game::{Game, Player, Shot, PLAYERS};
+
+    #[test]
+    fn chances_follow_the_shots() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(5));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(6));
+
+        // Nothing is known yet: the middle is likelier than a corner, and
+        // the chances add up to every ship cell
+        let view = PlayerView::new(&game, Player::Alpha);
+        let mut rng = Rng::new(7);
+        let chances = hit_chances(&view, game.rules, &mut rng);
+        let middle = Coord::new(4, 4).index();
+        assert!(chances[middle] > chances[0]);
+        let sum: f64 = chances.iter().sum();
+        assert!((sum - 17.0).abs() < 1e-9);
+        assert!(best_shot(&chances, &view).unwrap().1 >= chances[middle]);
+
+        // Next to a hit is better than anywhere else
+        let ship = game.ships_beta[0];
+        let hit = Coord::from_board(ship).unwrap();
+        assert_eq!(game.shoot(Player::Alpha, hit.to_board()), Ok(Shot::Hit));
+        let view = PlayerView::new(&game, Player::Alpha);
+        let chances = hit_chances(&view, game.rules, &mut rng);
+        assert_eq!(chances[hit.index()], 0.0);
+        let (best, _) = best_shot(&chances, &view).unwrap();
+        let (dx, dy) = (best.x.abs_diff(hit.x), best.y.abs_diff(hit.y));
+        assert_eq!(dx + dy, 1, "{} is not next to {}", best, hit);
+    }
+
+    #[test]
+    fn cells_of_a_board() {
+        let board = Coord::new(0, 0).to_board() | Coord::new(9, 9).to_board();
+        assert_eq!(cells(board).collect::<Vec<_>>(), [0, 99]);
+        assert_eq!(cells(0).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn reviews_count_luck_and_turning_points() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(2));
+
+        // Beta hits first, then Alpha hits twice in a row
+        let alpha = Coord::from_board(game.ships_beta[0]).unwrap();
+        let beta = Coord::from_board(game.ships_alpha[0]).unwrap();
+        let water = Coord::all()
+            .find(|cell| game.get_board(Player::Alpha) & cell.to_board() == 0)
+            .unwrap();
+        let next = Coord::all()
+            .filter(|cell| game.ships_beta[0] & cell.to_board() != 0)
+            .nth(1)
+            .unwrap();
+        for (player, cell) in [
+            (Player::Beta, beta),
+            (Player::Alpha, alpha),
+            (Player::Beta, water),
+            (Player::Alpha, next),
+        ] {
+            game.shoot(player, cell.to_board()).unwrap();
+        }
+
+        let review = Review::new(&game);
+        assert_eq!(review.shots.len(), 4);
+        assert_eq!(review.hits(Player::Alpha), 2);
+        assert_eq!(review.turning_points, [3]);
+        assert!(review.luck(Player::Alpha) > 0.0);
+        assert_eq!(review.hits(Player::Beta), 1);
+        assert!(review.shots.iter().all(|shot| shot.quality() <= 1.0));
+
+        let summary = review.summary();
+        assert!(summary[0].starts_with("Player alpha: 2 hits from 2 shots"));
+        assert!(summary[2].starts_with("Turning point: shot 4, Player alpha"));
+    }
+}
@@ -121,6 +121,79 @@ pub const fn transpose(input: u128) -> u128 {
     result
 }
 
+/// Rotates a ship around its anchor, the top-left-most cell of the ship, so a
+/// horizontal ship becomes vertical and vice versa. Parts that would stick
+/// out of the board push the whole ship back inside.
+#[inline(always)]
+pub const fn rotate(ship: u128) -> u128 {
+    debug_assert!(ship & !BOARD_MASK == 0);
+    if ship == 0 {
+        return 0;
+    }
+
+    let size = BOARD_SIZE as isize;
+    let anchor = ship.leading_zeros() as isize;
+    let (ax, ay) = (anchor % size, anchor / size);
+
+    // The first pass only finds how far the rotated ship sticks out
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (ax, ax, ay, ay);
+    let mut y = 0;
+    while y < size {
+        let mut x = 0;
+        while x < size {
+            if board_get(ship, x as usize, y as usize) {
+                let (nx, ny) = (ax + y - ay, ay + x - ax);
+                if nx < min_x {
+                    min_x = nx;
+                }
+                if nx > max_x {
+                    max_x = nx;
+                }
+                if ny < min_y {
+                    min_y = ny;
+                }
+                if ny > max_y {
+                    max_y = ny;
+                }
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+
+    let shift_x = if min_x < 0 {
+        -min_x
+    } else if max_x >= size {
+        size - 1 - max_x
+    } else {
+        0
+    };
+    let shift_y = if min_y < 0 {
+        -min_y
+    } else if max_y >= size {
+        size - 1 - max_y
+    } else {
+        0
+    };
+
+    let mut result = 0;
+    let mut y = 0;
+    while y < size {
+        let mut x = 0;
+        while x < size {
+            if board_get(ship, x as usize, y as usize) {
+                let nx = ax + y - ay + shift_x;
+                let ny = ay + x - ax + shift_y;
+                result = board_set(result, nx as usize, ny as usize, true);
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+
+    result
+}
+
 #[inline(always)]
 pub const fn ship_size(mut ship: u128) -> usize {
     let mut length: usize = 0;
@@ -134,14 +207,35 @@ pub const fn ship_size(mut ship: u128) -> usize {
 mod test {
     #[allow(unused_imports)]
     use super::*;
+    #[allow(unused_imports)]
+    use crate::board;
 
     #[test]
     fn surround_mask() {
         let ship = create_ship(3);
         let ship = wrapping_move(wrapping_move(ship, 2, Direction::Down), 1, Direction::Right);
-        assert_eq!(ship, 0b0000000000000000000001110000000000000000000000000000000000000000000000000000000000000000000000000000 << GAP);
+        assert_eq!(
+            ship,
+            board!(
+                "
+                . . . . . . . . . .
+                . . . . . . . . . .
+                . # # # . . . . . .
+                "
+            )
+        );
         let mask = create_surround_mask(ship);
-        assert_eq!(mask, 0b0000000000111110000011111000001111100000000000000000000000000000000000000000000000000000000000000000 << GAP);
+        assert_eq!(
+            mask,
+            board!(
+                "
+                . . . . . . . . . .
+                # # # # # . . . . .
+                # # # # # . . . . .
+                # # # # # . . . . .
+                "
+            )
+        );
     }
 
     #[test]
@@ -163,8 +257,21 @@ mod test {
 
     #[test]
     fn left_border_mask() {
-        let l: u128 = 0b1000000000100000000010000000001000000000100000000010000000001000000000100000000010000000001000000000 << GAP;
-        assert_eq!(LEF_BORDER_MASK, l);
+        let left = board!(
+            "
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            # . . . . . . . . .
+            "
+        );
+        assert_eq!(LEF_BORDER_MASK, left);
     }
 
     #[test]
@@ -196,13 +303,32 @@ mod test {
 
     #[test]
     fn move_1_down() {
-        assert_eq!(
-            saturated_move(
-                0b00001_00000__00000_00000__00000_00000 << GAP,
-                Direction::Down
-            ),
-            0b00000_00000_00001_00000_00000_00000 << GAP
+        let ship = board!(
+            "
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . # . . . . .
+            "
         );
+        let moved = board!(
+            "
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . . . . . . .
+            . . . . # . . . . .
+            "
+        );
+        assert_eq!(saturated_move(ship, Direction::Down), moved);
     }
 
     #[test]
@@ -212,4 +338,29 @@ mod test {
             0b00001_00000 << 1
         );
     }
+
+    #[test]
+    fn rotate_keeps_anchor() {
+        let ship = wrapping_move(create_ship(3), 7, Direction::Right);
+        let ship = wrapping_move(ship, 2, Direction::Down);
+        let expected = board_set(board_set(board_set(0, 7, 2, true), 7, 3, true), 7, 4, true);
+        assert_eq!(rotate(ship), expected);
+        assert_eq!(rotate(rotate(ship)), ship);
+    }
+
+    #[test]
+    fn rotate_clamps_into_board() {
+        let ship = wrapping_move(transpose(create_ship(5)), 7, Direction::Right);
+        assert_eq!(
+            rotate(ship),
+            wrapping_move(create_ship(5), 5, Direction::Right)
+        );
+
+        let ship = wrapping_move(create_ship(4), 8, Direction::Down);
+        let ship = wrapping_move(ship, 1, Direction::Right);
+        let rotated = rotate(ship);
+        assert_eq!(ship_size(rotated), 4);
+        assert!(board_get(rotated, 1, 9));
+        assert!(board_get(rotated, 1, 6));
+    }
 }
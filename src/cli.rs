@@ -0,0 +1,561 @@
+//! Command line parsing. Without a subcommand the game is played, so
+//! `battleship --vs-ai` works like `battleship play --vs-ai`.
+
+use std::path::PathBuf;
+
+use battleship::ai::{strategy_by_name, STRATEGY_NAMES};
+use battleship::net;
+use battleship::rules::{Rules, RULES};
+use battleship::stats::{is_valid_name, MAX_NAME};
+use battleship::theme::{Palette, Theme, THEMES};
+
+pub const USAGE: &str = "\
+usage: battleship [play] [OPTIONS]
+       battleship replay FILE [DISPLAY OPTIONS]
+       battleship simulate [--games N] [--seed N] [--rules NAME] [--alpha AI] [--beta AI]
+                           [--output FILE]
+       battleship host [ADDRESS] [DISPLAY OPTIONS] [--rules NAME] [--seed N]
+       battleship join ADDRESS [DISPLAY OPTIONS] [--name NAME]
+       battleship analyze FILE
+       battleship export [FILE] [--text]
+       battleship import FILE
+       battleship stats [NAME]
+       battleship tournament [--games N] [--seed N] [--rules NAME]
+       battleship ratings
+       battleship --help | --version
+
+Commands:
+  play       play a game in this terminal, the default
+  replay     play back a game record shot by shot, or show the boards of a
+             saved game
+  simulate   let computer players play many games and print who won, --output
+             adds the games to a file of binary records
+  host       wait for a player to join over the network, port 7777 by default
+  join       join a game hosted on another computer
+  analyze    print what each player knew of a game and review every shot
+  export     print a saved game as JSON, the current save by default, or
+             with --text as plain text boards for bug reports
+  import     make a game in JSON the saved game, to resume it with play
+  stats      print the statistics of every player, or of one
+  tournament let every computer player play every other one, 10 games a
+             pair by default, and rate the games
+  ratings    print the players from the highest rating down
+
+Play options:
+  --vs-ai            play against the computer
+  --ai NAME          play against the computer using a strategy
+  --rules NAME       rules of the game
+  --seed N           seed for random layouts and computer shots
+  --fleet FILE       place your fleet from a JSON layout
+  --name NAME        name your statistics and rating are kept under, $USER
+                     by default
+  --opponent NAME    name of the second player at this terminal, guest by
+                     default
+  --analysis         after the game, compare the shots with the best ones,
+                     and show the luck of both players and turning points
+
+Display options:
+  --accessible       line oriented interface for screen readers
+  --theme NAME       builtin theme, instead of the theme file
+  --colorblind       same as --palette colorblind
+  --palette NAME     colors that stay apart with color blindness
+
+Finished games are recorded in the games directory of the data directory,
+~/.local/share/battleship by default, to be played back with replay.
+Statistics and ratings of the players are kept in stats.txt and ratings.txt
+there.
+";
+
+/// What the program was asked to do.
+pub enum Command {
+    Play(PlayOptions),
+    Replay {
+        file: PathBuf,
+        display: DisplayOptions,
+    },
+    Simulate(SimulateOptions),
+    Host {
+        address: String,
+        options: PlayOptions,
+    },
+    Join {
+        address: String,
+        display: DisplayOptions,
+        name: Option<String>,
+    },
+    Analyze {
+        file: PathBuf,
+    },
+    /// Without a file the save slot is exported.
+    Export {
+        file: Option<PathBuf>,
+        /// Plain text boards instead of JSON, see [`battleship::text`].
+        text: bool,
+    },
+    Import {
+        file: PathBuf,
+    },
+    /// Without a name every player is shown.
+    Stats {
+        name: Option<String>,
+    },
+    Tournament(TournamentOptions),
+    Ratings,
+    Help,
+    Version,
+}
+
+/// How the game is shown.
+#[derive(Default)]
+pub struct DisplayOptions {
+    /// Line oriented interface for screen readers, see [`battleship::accessible`].
+    pub accessible: bool,
+    pub theme: Option<&'static str>,
+    pub palette: Option<Palette>,
+}
+
+#[derive(Default)]
+pub struct PlayOptions {
+    pub display: DisplayOptions,
+    pub rules: Rules,
+    pub seed: Option<u64>,
+    /// Strategy of the computer playing Beta, if any.
+    pub ai: Option<&'static str>,
+    /// Layout of the local player's fleet, see [`battleship::export`].
+    pub fleet: Option<PathBuf>,
+    /// Profile of the local player, see [`battleship::stats`].
+    pub name: Option<String>,
+    /// Profile of Beta when two players share the terminal.
+    pub opponent: Option<String>,
+    /// Review the shots after the game, see [`battleship::analysis`].
+    pub analysis: bool,
+}
+
+pub struct SimulateOptions {
+    pub games: usize,
+    pub seed: Option<u64>,
+    pub rules: Rules,
+    /// Strategies of Alpha and Beta.
+    pub strategies: [&'static str; 2],
+    /// File the finished games are appended to, see [`battleship::codec`].
+    pub output: Option<PathBuf>,
+}
+
+impl Default for SimulateOptions {
+    fn default() -> Self {
+        SimulateOptions {
+            games: 100,
+            seed: None,
+            rules: Rules::default(),
+            strategies: ["hunt", "hunt"],
+            output: None,
+        }
+    }
+}
+
+pub struct TournamentOptions {
+    /// Games each pair of computer players plays.
+    pub games: usize,
+    pub seed: Option<u64>,
+    pub rules: Rules,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            games: 10,
+            seed: None,
+            rules: Rules::default(),
+        }
+    }
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = Args {
+        args: args.into_iter().collect::<Vec<_>>().into_iter(),
+    };
+    let command = args.peek_positional();
+
+    match command.as_deref() {
+        None | Some("play") => match args.args.as_slice().first().map(String::as_str) {
+            Some("--help" | "-h") if command.is_none() => Ok(Command::Help),
+            Some("--version" | "-V") if command.is_none() => Ok(Command::Version),
+            _ => Ok(Command::Play(parse_play(&mut args, true)?)),
+        },
+        Some("replay") => {
+            let file = PathBuf::from(args.positional("replay needs a file")?);
+            let mut display = DisplayOptions::default();
+            while let Some(arg) = args.next() {
+                if !parse_display(&mut display, &arg, &mut args)? {
+                    return args.unknown(&arg);
+                }
+            }
+            Ok(Command::Replay { file, display })
+        }
+        Some("simulate") => {
+            let mut options = SimulateOptions::default();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--games" => options.games = args.games()?,
+                    "--seed" => options.seed = Some(args.number("--seed")?),
+                    "--rules" => options.rules = args.rules()?,
+                    "--alpha" => options.strategies[0] = args.strategy("--alpha")?,
+                    "--beta" => options.strategies[1] = args.strategy("--beta")?,
+                    "--output" => options.output = Some(PathBuf::from(args.value("--output")?)),
+                    _ => return args.unknown(&arg),
+                }
+            }
+            Ok(Command::Simulate(options))
+        }
+        Some("host") => {
+            let address = match args.peek_positional() {
+                Some(address) => net::with_port(&address),
+                None => format!("0.0.0.0:{}", net::DEFAULT_PORT),
+            };
+            let options = parse_play(&mut args, false)?;
+            Ok(Command::Host { address, options })
+        }
+        Some("join") => {
+            let address = net::with_port(&args.positional("join needs an address")?);
+            let mut display = DisplayOptions::default();
+            let mut name = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--name" => name = Some(args.name("--name")?),
+                    "--accessible" => return args.unknown(&arg),
+                    _ if parse_display(&mut display, &arg, &mut args)? => {}
+                    _ => return args.unknown(&arg),
+                }
+            }
+            Ok(Command::Join {
+                address,
+                display,
+                name,
+            })
+        }
+        Some("analyze") => {
+            let file = PathBuf::from(args.positional("analyze needs a file")?);
+            match args.next() {
+                Some(arg) => args.unknown(&arg),
+                None => Ok(Command::Analyze { file }),
+            }
+        }
+        Some("export") => {
+            let file = args.peek_positional().map(PathBuf::from);
+            let mut text = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--text" => text = true,
+                    _ => return args.unknown(&arg),
+                }
+            }
+            Ok(Command::Export { file, text })
+        }
+        Some("import") => {
+            let file = PathBuf::from(args.positional("import needs a file")?);
+            match args.next() {
+                Some(arg) => args.unknown(&arg),
+                None => Ok(Command::Import { file }),
+            }
+        }
+        Some("tournament") => {
+            let mut options = TournamentOptions::default();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--games" => options.games = args.games()?,
+                    "--seed" => options.seed = Some(args.number("--seed")?),
+                    "--rules" => options.rules = args.rules()?,
+                    _ => return args.unknown(&arg),
+                }
+            }
+            Ok(Command::Tournament(options))
+        }
+        Some("ratings") => match args.next() {
+            Some(arg) => args.unknown(&arg),
+            None => Ok(Command::Ratings),
+        },
+        Some("stats") => {
+            let name = args.peek_positional();
+            match args.next() {
+                Some(arg) => args.unknown(&arg),
+                None => Ok(Command::Stats { name }),
+            }
+        }
+        Some(command) => Err(format!("unknown command `{}`", command)),
+    }
+}
+
+/// Options of `play` and `host`. Network games have no computer player
+/// and no accessible mode.
+fn parse_play(args: &mut Args, local: bool) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => options.rules = args.rules()?,
+            "--seed" => options.seed = Some(args.number("--seed")?),
+            "--fleet" => options.fleet = Some(PathBuf::from(args.value("--fleet")?)),
+            "--name" => options.name = Some(args.name("--name")?),
+            "--analysis" => options.analysis = true,
+            "--opponent" if local => options.opponent = Some(args.name("--opponent")?),
+            "--vs-ai" if local => options.ai = Some("hunt"),
+            "--ai" if local => options.ai = Some(args.strategy("--ai")?),
+            "--accessible" if !local => return args.unknown(&arg),
+            _ if parse_display(&mut options.display, &arg, args)? => {}
+            _ => return args.unknown(&arg),
+        }
+    }
+
+    if options.ai.is_some() && options.opponent.is_some() {
+        return Err(String::from(
+            "--opponent is for two players at this terminal, not the computer",
+        ));
+    }
+    Ok(options)
+}
+
+/// Returns whether the argument was a display option.
+fn parse_display(display: &mut DisplayOptions, arg: &str, args: &mut Args) -> Result<bool, String> {
+    match arg {
+        "--accessible" => display.accessible = true,
+        "--colorblind" => display.palette = Some(Palette::Colorblind),
+        "--palette" => {
+            let name = args.value("--palette")?;
+            let palette =
+                Palette::by_name(&name).ok_or_else(|| format!("unknown palette `{}`", name))?;
+            display.palette = Some(palette);
+        }
+        "--theme" => {
+            let name = args.value("--theme")?;
+            let theme = Theme::by_name(&name).ok_or_else(|| {
+                let names: Vec<&str> = THEMES.iter().map(|theme| theme().name).collect();
+                format!("unknown theme `{}`, try {}", name, names.join(", "))
+            })?;
+            display.theme = Some(theme.name);
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+struct Args {
+    args: std::vec::IntoIter<String>,
+}
+
+impl Iterator for Args {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.args.next()
+    }
+}
+
+impl Args {
+    fn value(&mut self, flag: &str) -> Result<String, String> {
+        self.next().ok_or_else(|| format!("{} needs a value", flag))
+    }
+
+    fn positional(&mut self, missing: &str) -> Result<String, String> {
+        self.peek_positional().ok_or_else(|| String::from(missing))
+    }
+
+    /// Takes the next argument if it is not an option.
+    fn peek_positional(&mut self) -> Option<String> {
+        match self.args.as_slice().first() {
+            Some(arg) if !arg.starts_with('-') => self.next(),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self, flag: &str) -> Result<u64, String> {
+        let value = self.value(flag)?;
+        value
+            .parse()
+            .map_err(|_| format!("{} needs a number, not `{}`", flag, value))
+    }
+
+    fn games(&mut self) -> Result<usize, String> {
+        match self.number("--games")? {
+            0 => Err(String::from("--games needs at least 1")),
+            games => Ok(games as usize),
+        }
+    }
+
+    fn rules(&mut self) -> Result<Rules, String> {
+        let name = self.value("--rules")?;
+        Rules::by_name(&name).ok_or_else(|| {
+            let names: Vec<&str> = RULES.iter().map(|rules| rules.name).collect();
+            format!("unknown rules `{}`, try {}", name, names.join(", "))
+        })
+    }
+
+    fn name(&mut self, flag: &str) -> Result<String, String> {
+        let name = self.value(flag)?;
+        match is_valid_name(&name) {
+            true => Ok(name),
+            false => Err(format!(
+                "`{}` cannot be a name, it needs no spaces and at most {} bytes, \
+                 and the strategy names are taken",
+                name, MAX_NAME
+            )),
+        }
+    }
+
+    fn strategy(&mut self, flag: &str) -> Result<&'static str, String> {
+        let name = self.value(flag)?;
+        match strategy_by_name(&name) {
+            Some(strategy) => Ok(strategy.name()),
+            None => Err(format!(
+                "unknown computer player `{}`, try {}",
+                name,
+                STRATEGY_NAMES.join(", ")
+            )),
+        }
+    }
+
+    fn unknown<T>(&self, arg: &str) -> Result<T, String> {
+        Err(format!("unknown argument `{}`", arg))
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[allow(dead_code)]
+    fn parse(line: &str) -> Result<Command, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn play_is_the_default() {
+        let Ok(Command::Play(options)) = parse("--vs-ai --rules salvo --seed 7 --colorblind")
+        else {
+            panic!("expected play");
+        };
+        assert_eq!(options.ai, Some("hunt"));
+        assert_eq!(options.rules, Rules::SALVO);
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.display.palette, Some(Palette::Colorblind));
+
+        let Ok(Command::Play(options)) =
+            parse("play --ai random --theme ascii --accessible --name alice")
+        else {
+            panic!("expected play");
+        };
+        assert_eq!(options.ai, Some("random"));
+        assert_eq!(options.name.as_deref(), Some("alice"));
+        assert_eq!(options.display.theme, Some("ascii"));
+        assert!(options.display.accessible);
+
+        let Ok(Command::Play(options)) = parse("--opponent bob --analysis") else {
+            panic!("expected play");
+        };
+        assert_eq!(options.opponent.as_deref(), Some("bob"));
+        assert!(options.analysis);
+
+        assert!(matches!(parse(""), Ok(Command::Play(_))));
+        assert!(matches!(parse("--help"), Ok(Command::Help)));
+        assert!(matches!(parse("-V"), Ok(Command::Version)));
+    }
+
+    #[test]
+    fn subcommands() {
+        let Ok(Command::Simulate(options)) =
+            parse("simulate --games 5 --beta random --output games.bin")
+        else {
+            panic!("expected simulate");
+        };
+        assert_eq!(options.games, 5);
+        assert_eq!(options.strategies, ["hunt", "random"]);
+        assert_eq!(options.output, Some(PathBuf::from("games.bin")));
+
+        let Ok(Command::Host { address, options }) =
+            parse("host --rules relaxed --fleet fleet.json")
+        else {
+            panic!("expected host");
+        };
+        assert_eq!(address, "0.0.0.0:7777");
+        assert_eq!(options.rules, Rules::RELAXED);
+        assert_eq!(options.fleet, Some(PathBuf::from("fleet.json")));
+
+        let Ok(Command::Join { address, name, .. }) =
+            parse("join localhost --theme unicode --name bob")
+        else {
+            panic!("expected join");
+        };
+        assert_eq!(address, "localhost:7777");
+        assert_eq!(name.as_deref(), Some("bob"));
+
+        let Ok(Command::Replay { file, .. }) = parse("replay game.txt") else {
+            panic!("expected replay");
+        };
+        assert_eq!(file, PathBuf::from("game.txt"));
+        assert!(matches!(
+            parse("analyze game.txt"),
+            Ok(Command::Analyze { .. })
+        ));
+        assert!(matches!(
+            parse("export"),
+            Ok(Command::Export {
+                file: None,
+                text: false
+            })
+        ));
+        assert!(matches!(
+            parse("export save.bin --text"),
+            Ok(Command::Export {
+                file: Some(_),
+                text: true
+            })
+        ));
+        assert!(matches!(
+            parse("import game.json"),
+            Ok(Command::Import { .. })
+        ));
+        assert!(matches!(parse("stats"), Ok(Command::Stats { name: None })));
+        assert!(matches!(parse("ratings"), Ok(Command::Ratings)));
+        let Ok(Command::Tournament(options)) = parse("tournament --games 3 --rules salvo") else {
+            panic!("expected tournament");
+        };
+        assert_eq!(options.games, 3);
+        assert_eq!(options.rules, Rules::SALVO);
+        let Ok(Command::Stats { name }) = parse("stats alice") else {
+            panic!("expected stats");
+        };
+        assert_eq!(name.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn bad_arguments() {
+        for line in [
+            "fly",
+            "--rules",
+            "--rules chess",
+            "--seed many",
+            "--ai psychic",
+            "--theme neon",
+            "simulate --games 0",
+            "simulate --output",
+            "host --vs-ai",
+            "join",
+            "join localhost --accessible",
+            "replay",
+            "analyze a.txt b.txt",
+            "export a.bin b.bin",
+            "export --json",
+            "import",
+            "--fleet",
+            "--name",
+            "--name #1",
+            "stats alice bob",
+            "tournament --games 0",
+            "tournament --beta random",
+            "ratings alice",
+            "--ai random --opponent bob",
+            "host --opponent bob",
+        ] {
+            assert!(parse(line).is_err(), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! Compact binary encoding of a game and whose turn it is, for save files
+//! and for storing many simulated games. Numbers are big endian, and every
+//! record ends with a CRC-32 of the bytes before it:
+//!
+//! ```text
+//! offset  size  field
+//! 0       2     magic "BS"
+//! 2       1     version, 1
+//! 3       1     rules, index in `RULES`
+//! 4       1     turn: 0 Alpha, 1 Beta
+//! 5       1     conceded: 0 nobody, 1 Alpha, 2 Beta
+//! 6       10    ships of Alpha, then Beta, one byte each
+//! 16      26    shots of Alpha, then Beta, 13 bytes each
+//! 42      1     number of moves
+//! 43      n     moves, see `Move::to_byte`
+//! 43+n    4     CRC-32 (IEEE) of the bytes before
+//! ```
+//!
+//! A ship byte is the index of its first cell, with the top bit set when
+//! it goes down, or `ff` when the ship is not placed yet. A board has cell
+//! A1 in bit 3 of its first byte and J10 in the bottom bit of the last
+//! one; the top four bits are always clear.
+
+use core::fmt;
+
+use crate::constants::{BOARD_COUNT, GAP};
+use crate::coord::Coord;
+use crate::game::{ship_at, Game, MoveError, Player, PLAYERS, SHIPS_COUNT, SHIP_SIZES};
+use crate::log::{Move, MOVES_CAPACITY};
+use crate::rules::RULES;
+
+pub const MAGIC: [u8; 2] = *b"BS";
+pub const VERSION: u8 = 1;
+
+/// Bytes of one board, 100 cells rounded up.
+pub const BOARD_BYTES: usize = BOARD_COUNT.div_ceil(8);
+const MOVES_OFFSET: usize = 6 + 2 * SHIPS_COUNT + 2 * BOARD_BYTES + 1;
+const CHECKSUM_BYTES: usize = 4;
+/// The longest record, of a game with every cell shot.
+pub const MAX_SIZE: usize = MOVES_OFFSET + MOVES_CAPACITY + CHECKSUM_BYTES;
+
+const NO_SHIP: u8 = 0xff;
+const DOWN: u8 = 0x80;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// The output buffer cannot hold the record.
+    BufferTooSmall,
+    /// The input ends in the middle of a record.
+    Truncated,
+    /// The input is not a battleship record.
+    Magic,
+    /// The record was written by another version of the game.
+    Version(u8),
+    /// The record was damaged.
+    Checksum,
+    /// The record is intact but describes an impossible game.
+    Invalid(&'static str),
+    /// A move of the record could not have been played.
+    Move(MoveError),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::BufferTooSmall => write!(f, "buffer too small"),
+            CodecError::Truncated => write!(f, "record is cut short"),
+            CodecError::Magic => write!(f, "not a battleship record"),
+            CodecError::Version(version) => write!(f, "unsupported version {}", version),
+            CodecError::Checksum => write!(f, "record is damaged"),
+            CodecError::Invalid(reason) => write!(f, "{}", reason),
+            CodecError::Move(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// Writes the record of the game into the buffer and returns its length.
+/// Fails when a ship is not a straight line, which a `Game` built by its
+/// methods never has.
+pub fn encode(game: &Game, turn: Player, buf: &mut [u8]) -> Result<usize, CodecError> {
+    let mut writer = Writer::new(buf);
+
+    writer.bytes(&MAGIC)?;
+    writer.byte(VERSION)?;
+    let rules = RULES
+        .iter()
+        .position(|rules| *rules == game.rules)
+        .ok_or(CodecError::Invalid("custom rules"))?;
+    writer.byte(rules as u8)?;
+    writer.byte(player_to_byte(turn))?;
+    writer.byte(game.conceded.map_or(0, |player| player_to_byte(player) + 1))?;
+
+    for player in PLAYERS {
+        for (layer, ship) in game.get_ships(player).into_iter().enumerate() {
+            writer.ship(ship, SHIP_SIZES[layer])?;
+        }
+    }
+    for player in PLAYERS {
+        writer.board(game.get_shoots(player))?;
+    }
+
+    writer.byte(game.moves.len() as u8)?;
+    writer.bytes(game.moves.as_bytes())?;
+
+    writer.finish()
+}
+
+/// Reads the record at the start of the bytes. Returns the game, whose
+/// turn it is and the length of the record, so records can be read one
+/// after another.
+pub fn decode(bytes: &[u8]) -> Result<(Game, Player, usize), CodecError> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(CodecError::Magic);
+    }
+    match reader.byte()? {
+        VERSION => {}
+        version => return Err(CodecError::Version(version)),
+    }
+
+    // Check the whole record before trusting any of it
+    let moves = *bytes.get(MOVES_OFFSET - 1).ok_or(CodecError::Truncated)? as usize;
+    let len = MOVES_OFFSET + moves + CHECKSUM_BYTES;
+    verify_checksum(bytes.get(..len).ok_or(CodecError::Truncated)?)?;
+    if moves > MOVES_CAPACITY {
+        return Err(CodecError::Invalid("more moves than cells"));
+    }
+
+    let mut game = Game {
+        rules: *RULES
+            .get(reader.byte()? as usize)
+            .ok_or(CodecError::Invalid("unknown rules"))?,
+        ..Game::default()
+    };
+    let turn = player_from_byte(reader.byte()?)?;
+    // Conceding comes after the last move
+    let conceded = match reader.byte()? {
+        0 => None,
+        byte => Some(player_from_byte(byte - 1)?),
+    };
+
+    for player in PLAYERS {
+        let mut fleet = [0; SHIPS_COUNT];
+        for (ship, size) in fleet.iter_mut().zip(SHIP_SIZES) {
+            *ship = reader.ship(size)?;
+        }
+        // A game saved while placing has some layers still empty
+        if game.place_partial_fleet(player, fleet).is_err() {
+            return Err(CodecError::Invalid("ships are too close"));
+        }
+    }
+    let shots = [reader.board()?, reader.board()?];
+    reader.byte()?;
+
+    // Playing the moves again has to lead to the same shots
+    let mut replay = game;
+    for _ in 0..moves {
+        let mv =
+            Move::from_byte(reader.byte()?).ok_or(CodecError::Invalid("move off the board"))?;
+        replay.play_move(mv).map_err(CodecError::Move)?;
+    }
+    if [replay.shoots_alpha, replay.shoots_beta] != shots {
+        return Err(CodecError::Invalid("moves do not match the shots"));
+    }
+    replay.conceded = conceded;
+
+    Ok((replay, turn, len))
+}
+
+/// The record of the game as a vector.
+#[cfg(feature = "std")]
+pub fn to_vec(game: &Game, turn: Player) -> Result<Vec<u8>, CodecError> {
+    let mut buf = [0; MAX_SIZE];
+    let len = encode(game, turn, &mut buf)?;
+    Ok(buf[..len].to_vec())
+}
+
+pub fn player_to_byte(player: Player) -> u8 {
+    player.index() as u8
+}
+
+pub fn player_from_byte(byte: u8) -> Result<Player, CodecError> {
+    Player::from_index(usize::from(byte)).ok_or(CodecError::Invalid("unknown player"))
+}
+
+/// Checks the CRC-32 at the end of the bytes.
+pub fn verify_checksum(record: &[u8]) -> Result<(), CodecError> {
+    if record.len() < CHECKSUM_BYTES {
+        return Err(CodecError::Truncated);
+    }
+    let (body, checksum) = record.split_at(record.len() - CHECKSUM_BYTES);
+    match crc32(body).to_be_bytes() == checksum {
+        true => Ok(()),
+        false => Err(CodecError::Checksum),
+    }
+}
+
+/// Writes fields of a record into a buffer.
+pub struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Writer { buf, len: 0 }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> Result<(), CodecError> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(CodecError::BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    pub fn byte(&mut self, byte: u8) -> Result<(), CodecError> {
+        self.bytes(&[byte])
+    }
+
+    pub fn board(&mut self, board: u128) -> Result<(), CodecError> {
+        let bytes = (board >> GAP).to_be_bytes();
+        self.bytes(&bytes[bytes.len() - BOARD_BYTES..])
+    }
+
+    /// Writes a ship of the size in one byte, or an empty layer.
+    pub fn ship(&mut self, ship: u128, size: usize) -> Result<(), CodecError> {
+        let byte = match Coord::from_board(ship) {
+            None => NO_SHIP,
+            Some(start) if ship_at(start, size, false) == Some(ship) => start.index() as u8,
+            Some(start) if ship_at(start, size, true) == Some(ship) => start.index() as u8 | DOWN,
+            Some(_) => return Err(CodecError::Invalid("ship is not a straight line")),
+        };
+        self.byte(byte)
+    }
+
+    /// Appends the checksum of everything written and returns the length.
+    pub fn finish(mut self) -> Result<usize, CodecError> {
+        let checksum = crc32(&self.buf[..self.len]);
+        self.bytes(&checksum.to_be_bytes())?;
+        Ok(self.len)
+    }
+}
+
+/// Reads fields of a record, see [`Writer`].
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        let end = self.position + len;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(CodecError::Truncated)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn byte(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn board(&mut self) -> Result<u128, CodecError> {
+        let mut bytes = [0; 16];
+        bytes[16 - BOARD_BYTES..].copy_from_slice(self.take(BOARD_BYTES)?);
+        let board = u128::from_be_bytes(bytes);
+        match board >> BOARD_COUNT {
+            0 => Ok(board << GAP),
+            _ => Err(CodecError::Invalid("cell outside the board")),
+        }
+    }
+
+    /// Reads a ship of the size, 0 for an empty layer.
+    pub fn ship(&mut self, size: usize) -> Result<u128, CodecError> {
+        let byte = self.byte()?;
+        if byte == NO_SHIP {
+            return Ok(0);
+        }
+        Coord::from_index(usize::from(byte & !DOWN))
+            .and_then(|start| ship_at(start, size, byte & DOWN != 0))
+            .ok_or(CodecError::Invalid("ship off the board"))
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xEDB8_8320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 as used by zip and PNG.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!0, |crc: u32, &byte| {
+        CRC_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::ai::played;
+    #[allow(unused_imports)]
+    use crate::{rng::Rng, rules::Rules};
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let mut conceded = played(Rules::SALVO, 2, 30);
+        conceded.concede(Player::Beta);
+        // A cell fired at again is refused and leaves nothing in the log
+        let mut again = played(Rules::CLASSIC, 4, 20);
+        let last = again.moves.iter().last().unwrap();
+        assert!(again.shoot(last.player, last.cell.to_board()).is_err());
+
+        for game in [
+            Game::default(),
+            played(Rules::CLASSIC, 1, 0),
+            played(Rules::RELAXED, 1, 40),
+            played(Rules::CLASSIC, 3, 200),
+            conceded,
+            again,
+        ] {
+            let mut buf = [0; MAX_SIZE];
+            let len = encode(&game, Player::Beta, &mut buf).unwrap();
+            assert_eq!(len, MOVES_OFFSET + game.moves.len() + CHECKSUM_BYTES);
+
+            assert_eq!(decode(&buf[..len]), Ok((game, Player::Beta, len)));
+        }
+    }
+
+    #[test]
+    fn records_follow_each_other() {
+        let games = [played(Rules::CLASSIC, 4, 10), played(Rules::CLASSIC, 5, 20)];
+        let mut buf = [0; 2 * MAX_SIZE];
+        let first = encode(&games[0], Player::Alpha, &mut buf).unwrap();
+        let second = encode(&games[1], Player::Alpha, &mut buf[first..]).unwrap();
+
+        let (_, _, read) = decode(&buf).unwrap();
+        assert_eq!(read, first);
+        let (game, _, read) = decode(&buf[first..]).unwrap();
+        assert_eq!(read, second);
+        assert_eq!(game.moves, games[1].moves);
+    }
+
+    #[test]
+    fn rejects_damage() {
+        let game = played(Rules::CLASSIC, 6, 20);
+        let mut buf = [0; MAX_SIZE];
+        let len = encode(&game, Player::Alpha, &mut buf).unwrap();
+        let record = &buf[..len];
+
+        assert_eq!(decode(&record[..len - 1]), Err(CodecError::Truncated));
+        assert_eq!(decode(b"PK"), Err(CodecError::Magic));
+
+        let mut damaged = record.to_vec();
+        damaged[20] ^= 0x10;
+        assert_eq!(decode(&damaged), Err(CodecError::Checksum));
+
+        let mut newer = record.to_vec();
+        newer[2] = VERSION + 1;
+        assert_eq!(decode(&newer), Err(CodecError::Version(VERSION + 1)));
+
+        // Shots without any moves
+        let mut empty = [0; MAX_SIZE];
+        let len_empty = encode(&Game::default(), Player::Alpha, &mut empty).unwrap();
+        let mut forged = empty[..len_empty - CHECKSUM_BYTES].to_vec();
+        forged[16] = 0x08;
+        forged.extend(crc32(&forged).to_be_bytes());
+        assert_eq!(
+            decode(&forged),
+            Err(CodecError::Invalid("moves do not match the shots"))
+        );
+
+        // A shot that is not in the moves, with a valid checksum
+        let mut forged = record[..len - CHECKSUM_BYTES].to_vec();
+        forged[16 + BOARD_BYTES - 1] ^= 0x10;
+        forged.extend(crc32(&forged).to_be_bytes());
+        assert_eq!(
+            decode(&forged),
+            Err(CodecError::Invalid("moves do not match the shots"))
+        );
+
+        // Moves that could not have been played
+        let reforge = |change: &dyn Fn(&mut Vec<u8>)| {
+            let mut forged = record[..len - CHECKSUM_BYTES].to_vec();
+            change(&mut forged);
+            forged.extend(crc32(&forged).to_be_bytes());
+            decode(&forged)
+        };
+        assert_eq!(
+            reforge(&|forged| forged[MOVES_OFFSET + 2] = forged[MOVES_OFFSET]),
+            Err(CodecError::Move(MoveError::AlreadyShot))
+        );
+        assert_eq!(
+            reforge(&|forged| {
+                forged[MOVES_OFFSET - 1] = 0xff;
+                forged.resize(MOVES_OFFSET + 0xff, 0);
+            }),
+            Err(CodecError::Invalid("more moves than cells"))
+        );
+        assert_eq!(
+            reforge(&|forged| forged[7] = forged[6]),
+            Err(CodecError::Invalid("ships are too close"))
+        );
+        assert_eq!(
+            reforge(&|forged| forged[16] |= 0x80),
+            Err(CodecError::Invalid("cell outside the board"))
+        );
+
+        let mut unplaced = Game::default();
+        unplaced.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        unplaced
+            .shoot(Player::Alpha, crate::constants::CELL)
+            .unwrap();
+        let len = encode(&unplaced, Player::Beta, &mut buf).unwrap();
+        assert_eq!(
+            decode(&buf[..len]),
+            Err(CodecError::Move(MoveError::NoFleet))
+        );
+
+        let mut small = [0; 20];
+        assert_eq!(
+            encode(&game, Player::Alpha, &mut small),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Directory with the user's configuration files, following the XDG base
+/// directory spec: `$XDG_CONFIG_HOME/battleship`, or `~/.config/battleship`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(base.join("battleship"))
+}
+
+/// Directory for saved games and other data the game writes:
+/// `$XDG_DATA_HOME/battleship`, or `~/.local/share/battleship`.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+
+    Some(base.join("battleship"))
+}
+
+/// Reads a file from the configuration directory. A missing file is not an
+/// error, it just means the defaults are used.
+pub fn read_config(file: &str) -> Result<Option<String>, ConfigError> {
+    let Some(path) = config_dir().map(|dir| dir.join(file)) else {
+        return Ok(None);
+    };
+
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(ConfigError::Io(err)),
+    }
+}
+
+/// One `name = value` line of a configuration file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub line: usize,
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+/// Splits a configuration file into `name = value` entries. Empty lines and
+/// lines starting with `#` are skipped.
+pub fn parse_entries(text: &str) -> Result<Vec<Entry<'_>>, ConfigError> {
+    let mut entries = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(ConfigError::syntax(line_number, "expected `name = value`"));
+        };
+
+        entries.push(Entry {
+            line: line_number,
+            name: name.trim(),
+            value: value.trim(),
+        });
+    }
+
+    Ok(entries)
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl ConfigError {
+    pub fn syntax(line: usize, message: impl Into<String>) -> Self {
+        ConfigError::Syntax {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "cannot read config: {}", err),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn entries_skip_comments() {
+        let text = "# comment\n\n up = k  w \nconfirm=enter\n";
+        let entries = parse_entries(text).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            Entry {
+                line: 3,
+                name: "up",
+                value: "k  w"
+            }
+        );
+        assert_eq!(entries[1].name, "confirm");
+        assert_eq!(entries[1].value, "enter");
+    }
+
+    #[test]
+    fn entry_without_value() {
+        match parse_entries("up k") {
+            Err(ConfigError::Syntax { line: 1, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::board_api::{board_get, board_set};
+use crate::constants::BOARD_SIZE;
+
+/// A board cell in the notation printed around the boards: a column letter
+/// and a row number, like `C5`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Coord {
+    /// Column, `A` is 0.
+    pub x: usize,
+    /// Row, `1` is 0.
+    pub y: usize,
+}
+
+impl Coord {
+    pub const fn new(x: usize, y: usize) -> Coord {
+        Coord { x, y }
+    }
+
+    /// Board with just this cell set.
+    pub const fn to_board(self) -> u128 {
+        board_set(0, self.x, self.y, true)
+    }
+
+    /// First cell of the board in reading order, if any.
+    pub fn from_board(board: u128) -> Option<Coord> {
+        Coord::all().find(|coord| board_get(board, coord.x, coord.y))
+    }
+
+    /// Position in reading order, `A1` is 0 and `J10` is 99.
+    pub const fn index(self) -> usize {
+        self.y * BOARD_SIZE + self.x
+    }
+
+    pub const fn from_index(index: usize) -> Option<Coord> {
+        match index < BOARD_SIZE * BOARD_SIZE {
+            true => Some(Coord::new(index % BOARD_SIZE, index / BOARD_SIZE)),
+            false => None,
+        }
+    }
+
+    /// All cells of the board, row by row.
+    pub fn all() -> impl Iterator<Item = Coord> {
+        (0..BOARD_SIZE).flat_map(|y| (0..BOARD_SIZE).map(move |x| Coord::new(x, y)))
+    }
+
+    pub fn column_name(x: usize) -> char {
+        (b'A' + x as u8) as char
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Coord::column_name(self.x), self.y + 1)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseCoordError;
+
+impl fmt::Display for ParseCoordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a column A to J and a row 1 to {}, like C5",
+            BOARD_SIZE
+        )
+    }
+}
+
+/// Parses `C5` or `c5`.
+impl FromStr for Coord {
+    type Err = ParseCoordError;
+
+    fn from_str(text: &str) -> Result<Coord, ParseCoordError> {
+        let mut chars = text.chars();
+        let column = chars.next().ok_or(ParseCoordError)?.to_ascii_uppercase();
+        let row = chars.as_str();
+
+        if !column.is_ascii_uppercase() || !row.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseCoordError);
+        }
+        let row: usize = row.parse().map_err(|_| ParseCoordError)?;
+        let x = usize::from(column as u8 - b'A');
+        if x >= BOARD_SIZE || row == 0 || row > BOARD_SIZE {
+            return Err(ParseCoordError);
+        }
+
+        Ok(Coord::new(x, row - 1))
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::constants::CELL;
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!("C5".parse(), Ok(Coord::new(2, 4)));
+        assert_eq!("j10".parse(), Ok(Coord::new(9, 9)));
+        assert_eq!(Coord::new(0, 9).to_string(), "A10");
+        for text in ["", "C", "5C", "K1", "A0", "A11", "A+1", "ж1"] {
+            assert_eq!(text.parse::<Coord>(), Err(ParseCoordError), "{}", text);
+        }
+    }
+
+    #[test]
+    fn board_round_trip() {
+        assert_eq!(Coord::new(0, 0).to_board(), CELL);
+        assert_eq!(Coord::from_board(CELL), Some(Coord::new(0, 0)));
+        for coord in Coord::all() {
+            assert_eq!(Coord::from_board(coord.to_board()), Some(coord));
+        }
+        assert_eq!(Coord::from_board(0), None);
+    }
+
+    #[test]
+    fn index_round_trip() {
+        for (index, coord) in Coord::all().enumerate() {
+            assert_eq!(coord.index(), index);
+            assert_eq!(Coord::from_index(index), Some(coord));
+        }
+        assert_eq!(Coord::from_index(100), None);
+    }
+}
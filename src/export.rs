@@ -0,0 +1,531 @@
+//! Games and fleet layouts as JSON, for scripts and other tools. Cells are
+//! written like on screen, `C5`, and import checks everything: unknown or
+//! missing keys, ship shapes and placement, and that the moves really lead
+//! to the results and the winner written down.
+//!
+//! A game:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "rules": "classic",
+//!   "turn": "beta",
+//!   "conceded": null,
+//!   "winner": null,
+//!   "fleets": {
+//!     "alpha": [{"ship": "carrier", "cells": ["A1", "B1", "C1", "D1", "E1"]}],
+//!     "beta": []
+//!   },
+//!   "moves": [{"player": "alpha", "cell": "C5", "result": "miss"}]
+//! }
+//! ```
+//!
+//! `rules` is one of the rule names, `turn` the player who shoots next and
+//! `conceded` and `winner` a player or `null`. A fleet lists the ships
+//! placed so far, each at most once, with their cells across or down. The
+//! moves are every shot in order, with `result` one of `miss`, `hit` or
+//! `sunk`.
+//!
+//! A fleet layout is `{"version": 1, "fleet": [...]}`, with all five ships.
+
+use std::fmt;
+
+use crate::coord::Coord;
+use crate::game::{
+    is_ship_shape, Game, MoveError, Player, Shot, PLAYERS, SHIPS_COUNT, SHIP_NAMES, SHIP_SIZES,
+};
+use crate::json::{self, JsonError, Value};
+use crate::log::{Move, MOVES_CAPACITY};
+use crate::rules::{Rules, RULES};
+
+pub const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    Json(JsonError),
+    /// Valid JSON that is not a valid game. The path points at the value,
+    /// like `moves[3].cell`.
+    Schema {
+        path: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Json(err) => write!(f, "{}", err),
+            ImportError::Schema { path, message } if path.is_empty() => write!(f, "{}", message),
+            ImportError::Schema { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<JsonError> for ImportError {
+    fn from(err: JsonError) -> Self {
+        ImportError::Json(err)
+    }
+}
+
+pub fn game_to_json(game: &Game, turn: Player) -> Value {
+    // Results come from playing the moves again on the fleets
+    let moves = game
+        .replay()
+        .map(|(mv, shot, _)| {
+            let result = match shot {
+                Shot::Miss => "miss",
+                Shot::Hit => "hit",
+                Shot::Sunk(_) => "sunk",
+            };
+            Value::object([
+                ("player", player_to_json(mv.player)),
+                ("cell", Value::string(mv.cell.to_string())),
+                ("result", Value::string(result)),
+            ])
+        })
+        .collect();
+
+    Value::object([
+        ("version", Value::Number(VERSION.into())),
+        ("rules", Value::string(game.rules.name)),
+        ("turn", player_to_json(turn)),
+        (
+            "conceded",
+            game.conceded.map_or(Value::Null, player_to_json),
+        ),
+        (
+            "winner",
+            game.get_winner().map_or(Value::Null, player_to_json),
+        ),
+        (
+            "fleets",
+            Value::object([
+                ("alpha", ships_to_json(&game.ships_alpha)),
+                ("beta", ships_to_json(&game.ships_beta)),
+            ]),
+        ),
+        ("moves", Value::Array(moves)),
+    ])
+}
+
+pub fn game_from_json(text: &str) -> Result<(Game, Player), ImportError> {
+    let root = json::parse(text)?;
+    let mut fields = Fields::new(&root, String::new())?;
+    check_version(&mut fields)?;
+
+    let (rules, path) = fields.string("rules")?;
+    let rules = Rules::by_name(rules).ok_or_else(|| {
+        let names: Vec<&str> = RULES.iter().map(|rules| rules.name).collect();
+        schema(path, format!("unknown rules, try {}", names.join(", ")))
+    })?;
+    let mut game = Game {
+        rules,
+        ..Game::default()
+    };
+
+    let (turn, turn_path) = fields.take("turn")?;
+    let turn = player_from_json(turn, turn_path.clone())?;
+    let (conceded, conceded_path) = fields.take("conceded")?;
+    let conceded = match conceded {
+        Value::Null => None,
+        value => Some(player_from_json(value, conceded_path.clone())?),
+    };
+    let (winner, winner_path) = fields.take("winner")?;
+    let winner = match winner {
+        Value::Null => None,
+        value => Some(player_from_json(value, winner_path.clone())?),
+    };
+
+    let (fleets, path) = fields.take("fleets")?;
+    let mut fleets = Fields::new(fleets, path)?;
+    for player in PLAYERS {
+        let (ships, path) = fields_array(&mut fleets, player.name())?;
+        for (layer, ship) in ships_from_json(ships, &path)? {
+            if game.add_ship(player, ship, layer).is_err() {
+                return Err(schema(
+                    path,
+                    format!("the {} is too close to another ship", SHIP_NAMES[layer]),
+                ));
+            }
+        }
+    }
+    fleets.finish()?;
+
+    let (moves, path) = fields_array(&mut fields, "moves")?;
+    if moves.len() > MOVES_CAPACITY {
+        return Err(schema(path, "more moves than cells"));
+    }
+    for (i, mv) in moves.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        let mut mv = Fields::new(mv, path.clone())?;
+        let (player, player_path) = mv.take("player")?;
+        let player = player_from_json(player, player_path)?;
+        let (cell, cell_path) = mv.take("cell")?;
+        let cell = cell_from_json(cell, cell_path.clone())?;
+        let shot = game
+            .play_move(Move { player, cell })
+            .map_err(|err| match err {
+                MoveError::AlreadyShot => schema(cell_path, err.to_string()),
+                _ => schema(path, err.to_string()),
+            })?;
+
+        let (result, result_path) = mv.string("result")?;
+        let expected = match shot {
+            Shot::Miss => "miss",
+            Shot::Hit => "hit",
+            Shot::Sunk(_) => "sunk",
+        };
+        if result != expected {
+            return Err(schema(result_path, format!("the shot was a {}", expected)));
+        }
+        mv.finish()?;
+    }
+    fields.finish()?;
+
+    if let Some(player) = conceded {
+        if game.is_over() {
+            return Err(schema(conceded_path, "the game was already over"));
+        }
+        game.concede(player);
+    }
+    if winner != game.get_winner() {
+        return Err(schema(winner_path, "does not match the fleets and moves"));
+    }
+    match game.turn {
+        Some((player, _)) if player != turn && !game.is_over() => {
+            Err(schema(turn_path, "does not match the moves"))
+        }
+        _ => Ok((game, turn)),
+    }
+}
+
+pub fn fleet_to_json(ships: &[u128; SHIPS_COUNT]) -> Value {
+    Value::object([
+        ("version", Value::Number(VERSION.into())),
+        ("fleet", ships_to_json(ships)),
+    ])
+}
+
+/// Reads a whole fleet, placed by the rules.
+pub fn fleet_from_json(text: &str, rules: Rules) -> Result<[u128; SHIPS_COUNT], ImportError> {
+    let root = json::parse(text)?;
+    let mut fields = Fields::new(&root, String::new())?;
+    check_version(&mut fields)?;
+
+    let (ships, path) = fields_array(&mut fields, "fleet")?;
+    let mut fleet = [0; SHIPS_COUNT];
+    for (layer, ship) in ships_from_json(ships, &path)? {
+        fleet[layer] = ship;
+    }
+    if let Some(layer) = fleet.iter().position(|&ship| ship == 0) {
+        return Err(schema(
+            path,
+            format!("the {} is missing", SHIP_NAMES[layer]),
+        ));
+    }
+    fields.finish()?;
+
+    let mut game = Game {
+        rules,
+        ..Game::default()
+    };
+    match game.place_fleet(Player::Alpha, fleet) {
+        Ok(()) => Ok(fleet),
+        Err(err) => Err(schema(path, err.to_string())),
+    }
+}
+
+fn ships_to_json(ships: &[u128; SHIPS_COUNT]) -> Value {
+    let ships = ships
+        .iter()
+        .enumerate()
+        .filter(|(_, &ship)| ship != 0)
+        .map(|(layer, &ship)| {
+            let cells = Coord::all()
+                .filter(|cell| ship & cell.to_board() != 0)
+                .map(|cell| Value::string(cell.to_string()))
+                .collect();
+            Value::object([
+                ("ship", Value::string(SHIP_NAMES[layer])),
+                ("cells", Value::Array(cells)),
+            ])
+        })
+        .collect();
+    Value::Array(ships)
+}
+
+/// Ships by layer, checked to be straight lines of their size.
+fn ships_from_json(ships: &[Value], path: &str) -> Result<Vec<(usize, u128)>, ImportError> {
+    let mut placed: Vec<(usize, u128)> = Vec::new();
+
+    for (i, ship) in ships.iter().enumerate() {
+        let mut fields = Fields::new(ship, format!("{}[{}]", path, i))?;
+        let (name, name_path) = fields.string("ship")?;
+        let layer = SHIP_NAMES
+            .iter()
+            .position(|&ship| ship == name)
+            .ok_or_else(|| {
+                let message = format!("unknown ship, try {}", SHIP_NAMES.join(", "));
+                schema(name_path.clone(), message)
+            })?;
+        if placed.iter().any(|&(other, _)| other == layer) {
+            return Err(schema(name_path, "the ship appears twice"));
+        }
+
+        let (cells, cells_path) = fields_array(&mut fields, "cells")?;
+        let mut board = 0;
+        for (j, cell) in cells.iter().enumerate() {
+            let cell_path = format!("{}[{}]", cells_path, j);
+            let cell = cell_from_json(cell, cell_path.clone())?.to_board();
+            if board & cell != 0 {
+                return Err(schema(cell_path, "the cell appears twice"));
+            }
+            board |= cell;
+        }
+        if cells.len() != SHIP_SIZES[layer] || !is_ship_shape(board, SHIP_SIZES[layer]) {
+            let message = format!(
+                "a {} is {} cells in a line, across or down",
+                SHIP_NAMES[layer], SHIP_SIZES[layer]
+            );
+            return Err(schema(cells_path, message));
+        }
+        fields.finish()?;
+
+        placed.push((layer, board));
+    }
+
+    Ok(placed)
+}
+
+fn check_version(fields: &mut Fields) -> Result<(), ImportError> {
+    match fields.take("version")? {
+        (Value::Number(version), _) if *version == f64::from(VERSION) => Ok(()),
+        (Value::Number(version), path) => {
+            Err(schema(path, format!("unsupported version {}", version)))
+        }
+        (value, path) => Err(schema(
+            path,
+            format!("expected a number, not {}", value.kind()),
+        )),
+    }
+}
+
+fn player_to_json(player: Player) -> Value {
+    Value::string(player.name())
+}
+
+fn player_from_json(value: &Value, path: String) -> Result<Player, ImportError> {
+    match value {
+        Value::String(name) => name.parse().map_err(|_| ()),
+        _ => Err(()),
+    }
+    .map_err(|_| schema(path, "expected \"alpha\" or \"beta\""))
+}
+
+fn cell_from_json(value: &Value, path: String) -> Result<Coord, ImportError> {
+    let Value::String(text) = value else {
+        return Err(schema(
+            path,
+            format!("expected a cell, not {}", value.kind()),
+        ));
+    };
+    text.parse().map_err(|err| schema(path, format!("{}", err)))
+}
+
+fn schema(path: String, message: impl Into<String>) -> ImportError {
+    ImportError::Schema {
+        path,
+        message: message.into(),
+    }
+}
+
+fn fields_array<'a>(
+    fields: &mut Fields<'a>,
+    key: &str,
+) -> Result<(&'a [Value], String), ImportError> {
+    match fields.take(key)? {
+        (Value::Array(items), path) => Ok((items, path)),
+        (value, path) => Err(schema(
+            path,
+            format!("expected an array, not {}", value.kind()),
+        )),
+    }
+}
+
+/// The keys of an object, each to be taken once. Keys left over are
+/// unknown and an error.
+struct Fields<'a> {
+    fields: Vec<(&'a str, &'a Value)>,
+    path: String,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, path: String) -> Result<Fields<'a>, ImportError> {
+        match value {
+            Value::Object(fields) => Ok(Fields {
+                fields: fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value))
+                    .collect(),
+                path,
+            }),
+            value => Err(schema(
+                path,
+                format!("expected an object, not {}", value.kind()),
+            )),
+        }
+    }
+
+    fn path(&self, key: &str) -> String {
+        match self.path.is_empty() {
+            true => String::from(key),
+            false => format!("{}.{}", self.path, key),
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Result<(&'a Value, String), ImportError> {
+        match self.fields.iter().position(|(other, _)| *other == key) {
+            Some(i) => Ok((self.fields.remove(i).1, self.path(key))),
+            None => Err(schema(self.path.clone(), format!("missing `{}`", key))),
+        }
+    }
+
+    fn string(&mut self, key: &str) -> Result<(&'a str, String), ImportError> {
+        match self.take(key)? {
+            (Value::String(text), path) => Ok((text, path)),
+            (value, path) => Err(schema(
+                path,
+                format!("expected a string, not {}", value.kind()),
+            )),
+        }
+    }
+
+    fn finish(self) -> Result<(), ImportError> {
+        match self.fields.first() {
+            Some((key, _)) => Err(schema(self.path(key), "unknown key")),
+            None => Ok(()),
+        }
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::ai::played;
+
+    #[test]
+    fn games_round_trip() {
+        let game = played(Rules::CLASSIC, 5, 60);
+        let text = game_to_json(&game, Player::Alpha).to_string();
+        assert_eq!(game_from_json(&text), Ok((game, Player::Alpha)));
+
+        let mut conceded = game;
+        conceded.concede(Player::Beta);
+        let text = game_to_json(&conceded, Player::Alpha).to_string();
+        assert!(text.contains("\"winner\": \"alpha\""));
+        assert_eq!(game_from_json(&text), Ok((conceded, Player::Alpha)));
+
+        let text = game_to_json(&Game::default(), Player::Alpha).to_string();
+        assert_eq!(game_from_json(&text), Ok((Game::default(), Player::Alpha)));
+    }
+
+    #[test]
+    fn fleets_round_trip() {
+        let game = played(Rules::CLASSIC, 5, 60);
+        let text = fleet_to_json(&game.ships_beta).to_string();
+        assert_eq!(fleet_from_json(&text, Rules::CLASSIC), Ok(game.ships_beta));
+        assert!(text.contains("\"ship\": \"patrol boat\""));
+    }
+
+    #[test]
+    fn rejects_invalid_games() {
+        let text = game_to_json(&played(Rules::CLASSIC, 5, 60), Player::Alpha).to_string();
+        let first_move = r#""player": "alpha",
+      "cell": "A1",
+      "result": "miss""#;
+        assert!(text.contains(first_move), "{}", text);
+
+        for (from, to, path) in [
+            ("\"version\": 1", "\"version\": 2", "version"),
+            ("\"rules\": \"classic\"", "\"rules\": \"chess\"", "rules"),
+            ("\"turn\": \"alpha\"", "\"turn\": \"gamma\"", "turn"),
+            ("\"turn\": \"alpha\"", "\"turn\": \"beta\"", "turn"),
+            (
+                "\"turn\": \"alpha\"",
+                "\"turn\": \"alpha\", \"extra\": 1",
+                "extra",
+            ),
+            ("\"conceded\": null,", "", ""),
+            ("\"winner\": null", "\"winner\": \"beta\"", "winner"),
+            ("\"cell\": \"A1\"", "\"cell\": \"K1\"", "moves[0].cell"),
+            ("\"cell\": \"A1\"", "\"cell\": 11", "moves[0].cell"),
+            (
+                "\"result\": \"miss\"",
+                "\"result\": \"sunk\"",
+                "moves[0].result",
+            ),
+            (
+                "\"ship\": \"carrier\"",
+                "\"ship\": \"battleship\"",
+                "fleets.alpha[0].cells",
+            ),
+            (
+                "\"ship\": \"carrier\"",
+                "\"ship\": \"canoe\"",
+                "fleets.alpha[0].ship",
+            ),
+        ] {
+            let changed = text.replacen(from, to, 1);
+            assert_ne!(changed, text, "{}", from);
+            match game_from_json(&changed) {
+                Err(ImportError::Schema { path: found, .. }) => assert_eq!(found, path, "{}", to),
+                other => panic!("{} gave {:?}", to, other),
+            }
+        }
+
+        // The same cell shot twice
+        let twice = text.replacen(
+            first_move,
+            &format!("{}\n    }},\n    {{\n      {}", first_move, first_move),
+            1,
+        );
+        assert!(game_from_json(&twice).is_err());
+        assert!(matches!(game_from_json("{"), Err(ImportError::Json(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_fleets() {
+        let ships = r#"{"ship": "carrier", "cells": ["A1", "B1", "C1", "D1", "E1"]},
+            {"ship": "battleship", "cells": ["A3", "B3", "C3", "D3"]},
+            {"ship": "destroyer", "cells": ["A5", "B5", "C5"]},
+            {"ship": "submarine", "cells": ["A7", "B7", "C7"]}"#;
+        let fleet = |last: &str| format!(r#"{{"version": 1, "fleet": [{}{}]}}"#, ships, last);
+
+        let good = fleet(r#", {"ship": "patrol boat", "cells": ["J9", "J10"]}"#);
+        assert!(fleet_from_json(&good, Rules::CLASSIC).is_ok());
+
+        for last in [
+            "",
+            r#", {"ship": "patrol boat", "cells": ["J9", "J9"]}"#,
+            r#", {"ship": "patrol boat", "cells": ["I9", "J10"]}"#,
+            r#", {"ship": "patrol boat", "cells": ["J9", "J10", "J8"]}"#,
+            r#", {"ship": "patrol boat", "cells": ["A2", "A3"]}"#,
+            r#", {"ship": "patrol boat", "cells": ["J9", "J10"], "color": "red"}"#,
+            r#", {"ship": "patrol boat"}"#,
+        ] {
+            assert!(
+                fleet_from_json(&fleet(last), Rules::CLASSIC).is_err(),
+                "{}",
+                last
+            );
+        }
+
+        // Touching ships are fine by the relaxed rules
+        let touching = fleet(r#", {"ship": "patrol boat", "cells": ["E2", "F2"]}"#);
+        assert!(fleet_from_json(&touching, Rules::CLASSIC).is_err());
+        assert!(fleet_from_json(&touching, Rules::RELAXED).is_ok());
+    }
+}
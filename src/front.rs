@@ -1,390 +1,950 @@
-use std::io::Read;
-use std::io::{self, Write};
-use termios::{tcsetattr, Termios, ECHO, ICANON, TCSANOW, VMIN, VTIME};
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
-use crate::board_api::{saturated_move, ship_size, Direction};
+use crate::board_api::{board_set, saturated_move, Direction};
 use crate::constants::CELL;
-use crate::game::SHIPS_COUNT;
+use crate::coord::Coord;
+use crate::game::{ship_title, Shot, SHIPS_COUNT, SHIP_NAMES, SHIP_SIZES};
+use crate::keys::{Action, KeyMap, ACTIONS};
+use crate::render::{Frame, Renderer};
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::term::{self, Key};
+use crate::theme::{CellKind, Style, Theme, CELL_KINDS, CELL_WIDTH};
+use crate::view::{PlayerView, ShipStatus};
 use crate::{
-    board_api::{board_get, create_ship, create_surround_mask, transpose},
+    board_api::{board_get, create_ship, create_surround_mask, rotate},
     constants::BOARD_SIZE,
-    game::{Game, Player}
+    game::{Game, Interrupt, Player},
 };
 
-// Base part of fiels. Represents something like [ ], [*], [~], [O]
-pub const CELL_SIZE: usize = 12; // color identifier (\u{001B}) + [ + color (2) + m + cell + color identifier (\u{001B}) + [ + 0 + m
-pub type Cell = [u8; CELL_SIZE];
-pub type OutputBuffer = [[Cell; BOARD_SIZE]; BOARD_SIZE];
-                                 //
-// cells:
-//  -     -- miss
-//  - [*] -- hit
-//  - [~] -- unknown
-//  - [O] -- your ship
-//  - [X] -- collided ship
-//  - [n] -- new ship
-//  - {+} -- crosshair
-//
-// colors :
-//   -     -- \033[00m   \033[0m
-//   - [*] -- \033[31m[*]\033[0m
-//   - [~] -- \033[34m[~]\033[0m
-//   - [O] -- \033[32m[O]\033[0m
-//   - [X] -- \033[33m[X]\033[0m
-//   - [n] -- \033[32m[o]\033[0m
-//   - {+} -- \033[32m{+}\033[0m
-
-const CELL_MISS: Cell = create_cell("\u{001B}[00m   \u{001B}[0m");
-const CELL_HIT: Cell = create_cell("\u{001B}[31m[*]\u{001B}[0m");
-const CELL_UNKNOWN: Cell = create_cell("\u{001B}[34m[~]\u{001B}[0m");
-const CELL_SHIP: Cell = create_cell("\u{001B}[32m[O]\u{001B}[0m");
-const CELL_COLLISION: Cell = create_cell("\u{001B}[33m[X]\u{001B}[0m");
-const CELL_NEW_SHIP: Cell = create_cell("\u{001B}[32m[n]\u{001B}[0m");
-const CELL_CROSSHAIR: Cell = create_cell("\u{001B}[33m{+}\u{001B}[0m");
-
-const fn create_cell(val: &str) -> Cell {
-    let mut cell = [0; CELL_SIZE];
-    let bytes = val.as_bytes();
-
-    assert!(bytes.len() == CELL_SIZE);
-
-    let mut i = 0;
-    while i < bytes.len() {
-        cell[i] = bytes[i];
-        i += 1;
-    }
-    cell
-}
+/// What is shown on each cell of a board. How it looks is decided by the
+/// theme when the board is drawn.
+pub type OutputBuffer = [[CellKind; BOARD_SIZE]; BOARD_SIZE];
+
+type Fleet = [ShipStatus; SHIPS_COUNT];
 
 #[derive(Default)]
 pub struct IO {
     pub lbuffer: OutputBuffer,
     pub rbuffer: OutputBuffer,
+    pub keys: KeyMap,
+    pub rng: Rng,
+    pub theme: Theme,
+    /// Set for network games, which cannot be resumed from a save.
+    pub saving_disabled: bool,
+    frame: Frame,
+    renderer: Renderer,
+    /// Terminal cells of A1 of the boards drawn on the current frame, for
+    /// mouse clicks.
+    board_cells: Vec<(usize, usize)>,
+    /// Cells of the left and right board highlighted as the enemy's last
+    /// shots, drawn over whatever the buffers show there.
+    highlights: [u128; 2],
 }
 
 impl IO {
-    fn display_left_board(&self) {
-        let buffer = self.lbuffer;
-        let mut stdout = io::stdout();
-
-        // Allocate the temporary buffer on the stack
-        let mut temp_buffer = [0u8; BOARD_SIZE * CELL_SIZE + 1]; // +1 for the newline character
-
-        for line in buffer.iter().take(BOARD_SIZE) {
-            let mut idx = 0;
-            for cell in line.iter().take(BOARD_SIZE) {
-                temp_buffer[idx..idx + CELL_SIZE].copy_from_slice(cell);
-                idx += CELL_SIZE;
-            }
-            // Add a newline character at the end of the line
-            temp_buffer[idx] = b'\n';
-            idx += 1;
+    fn display_left_board(&mut self, title: &str) {
+        self.board_cells = display_boards(
+            &mut self.frame,
+            &self.theme,
+            Layout::SideBySide,
+            &[(&self.lbuffer, 0, title)],
+            0,
+        );
+    }
+
+    /// Draws both boards with the status of Alpha's and Beta's fleet under
+    /// them.
+    fn display_two_boards(&mut self, fleets: [Fleet; 2], ltitle: &str, rtitle: &str) {
+        self.board_cells = display_boards(
+            &mut self.frame,
+            &self.theme,
+            Layout::for_width(terminal_width()),
+            &[
+                (&self.lbuffer, self.highlights[0], ltitle),
+                (&self.rbuffer, self.highlights[1], rtitle),
+            ],
+            STATUS_LINES,
+        );
+        self.display_players_ships_status(fleets);
+    }
+
+    /// Lists every ship under its board: its cells, name and whether it is
+    /// sunk. Damage is only shown as far as the viewer knows it.
+    fn display_players_ships_status(&mut self, fleets: [Fleet; 2]) {
+        for (fleet, (column, row)) in fleets.into_iter().zip(self.board_cells.clone()) {
+            for (layer, ship) in fleet.into_iter().enumerate() {
+                // Under the board, after an empty line
+                let row = row + BOARD_SIZE + 1 + layer;
+                let intact = match ship.revealed {
+                    true => CellKind::Ship,
+                    false => CellKind::Unknown,
+                };
+
+                let mut x = column;
+                for i in 0..ship.size {
+                    let cell = if i < ship.size - ship.damage {
+                        intact
+                    } else {
+                        CellKind::Hit
+                    };
+                    x = self.frame.glyphs(x, row, &self.theme.cell(cell).glyphs());
+                }
 
-            // Print the line
-            stdout.write_all(&temp_buffer[..idx]).unwrap();
+                let mut label = ship_title(layer);
+                if ship.sunk {
+                    label.push_str(", sunk");
+                }
+                let x = column + SHIP_SIZES[0] * CELL_WIDTH + 1;
+                self.frame.text(x, row, &label, Style::default());
+            }
         }
-        stdout.flush().unwrap();
     }
 
-    fn display_two_boards(&self) {
-        let lbuffer = self.lbuffer;
-        let rbuffer = self.rbuffer;
-        let mut stdout = io::stdout();
+    /// Fills the buffers with both boards as the viewer sees them.
+    fn render_view(&mut self, view: &PlayerView) {
+        let (own, enemy) = match view.player {
+            Player::Alpha => (&mut self.lbuffer, &mut self.rbuffer),
+            Player::Beta => (&mut self.rbuffer, &mut self.lbuffer),
+        };
+        render_current_player_board(own, view);
+        render_enemy_player_board(enemy, view);
+        self.highlights = [0; 2];
+    }
 
-        // Allocate the temporary buffer on the stack
-        let mut temp_buffer = [0u8; 2 * BOARD_SIZE * CELL_SIZE + 1 + 2]; // +1 for the newline character. +2 tab
+    /// Flashes the cell where a shot landed on the board with the given
+    /// index, drawn over what the buffers show now.
+    fn animate_shot(&mut self, view: &PlayerView, board: usize, shoot: u128) {
+        let saved = (self.lbuffer, self.rbuffer);
+        let (ltitle, rtitle) = board_titles(view.player);
+
+        for kind in SHOT_ANIMATION {
+            let buffer = match board {
+                0 => &mut self.lbuffer,
+                _ => &mut self.rbuffer,
+            };
+            render(buffer, shoot, kind);
+
+            self.clear();
+            self.display_two_boards(fleets_seen_by(view), ltitle, rtitle);
+            self.present();
+            thread::sleep(ANIMATION_FRAME);
+
+            (self.lbuffer, self.rbuffer) = saved;
+        }
+    }
+
+    pub fn display_scene_after_shoot(&mut self, view: &PlayerView, shoot: u128, shot: Shot) {
+        self.render_view(view);
+        self.animate_shot(view, view.player.other().index(), shoot);
+
+        let (ltitle, rtitle) = board_titles(view.player);
+        let text = describe_own_shot(shoot, shot);
+        self.until_enter(|io| {
+            io.display_two_boards(fleets_seen_by(view), ltitle, rtitle);
+            io.frame.println("");
+            io.frame.println(&text);
+        });
+    }
 
-        for y in 0..BOARD_SIZE {
-            let mut idx = 0;
-            for x in 0..BOARD_SIZE {
-                temp_buffer[idx..idx + CELL_SIZE].copy_from_slice(&lbuffer[y][x]);
-                idx += CELL_SIZE;
+    /// Reveals both fleets. Takes the views of both players, Alpha first.
+    /// The notes are shown below the result.
+    pub fn display_last_scene(
+        &mut self,
+        views: [&PlayerView; 2],
+        conceded: Option<Player>,
+        notes: &[String],
+    ) {
+        let [alpha, beta] = views;
+        render_current_player_board(&mut self.lbuffer, alpha);
+        render_current_player_board(&mut self.rbuffer, beta);
+        self.highlights = [0; 2];
+
+        let text = match (alpha.winner, conceded) {
+            (Some(Player::Alpha), Some(_)) => "Player Beta concedes, Player Alpha wins!",
+            (Some(Player::Beta), Some(_)) => "Player Alpha concedes, Player Beta wins!",
+            (Some(Player::Alpha), None) => "Player Alpha wins!",
+            (Some(Player::Beta), None) => "Player Beta wins!",
+            _ => "Nobody has won yet.",
+        };
+        let fleets = [alpha.own_fleet(), beta.own_fleet()];
+        self.until_enter(|io| {
+            io.display_two_boards(fleets, "Alpha's fleet", "Beta's fleet");
+            io.frame.println("");
+            io.frame.println(text);
+            for note in notes {
+                for line in wrap(note, TEXT_WIDTH) {
+                    io.frame.println(&line);
+                }
             }
+        });
+    }
 
-            // Add a tab characters at the end of the line
-            temp_buffer[idx] = b'\t';
-            idx += 1;
-            temp_buffer[idx] = b'\t';
-            idx += 1;
+    /// Shows paragraphs of text under a title, like the analysis of a
+    /// game.
+    pub fn display_report(&mut self, title: &str, paragraphs: &[String]) {
+        self.until_enter(|io| {
+            io.frame.println(title);
+            io.frame.println("");
+            for paragraph in paragraphs {
+                for line in wrap(paragraph, TEXT_WIDTH) {
+                    io.frame.println(&line);
+                }
+            }
+            io.frame.println("");
+        });
+    }
 
-            for x in 0..BOARD_SIZE {
-                temp_buffer[idx..idx + CELL_SIZE].copy_from_slice(&rbuffer[y][x]);
-                idx += CELL_SIZE;
+    /// Shows both fleets during a replay, with the last shot highlighted
+    /// on the board it hit. Returns false when the viewer quits to skip to
+    /// the end.
+    pub fn display_replay_step(
+        &mut self,
+        views: [&PlayerView; 2],
+        shoot: u128,
+        text: &str,
+    ) -> bool {
+        let [alpha, beta] = views;
+        render_current_player_board(&mut self.lbuffer, alpha);
+        render_current_player_board(&mut self.rbuffer, beta);
+        self.highlights = match alpha.incoming & shoot {
+            0 => [0, shoot],
+            _ => [shoot, 0],
+        };
+
+        let fleets = [alpha.own_fleet(), beta.own_fleet()];
+        loop {
+            self.clear();
+            self.display_two_boards(fleets, "Alpha's fleet", "Beta's fleet");
+            self.frame.println("");
+            self.frame.println(text);
+            self.frame
+                .println("Press enter for the next shot, or quit to skip to the end.");
+
+            let key = self.getkey();
+            match self.keys.action(key) {
+                Some(Action::Confirm) => return true,
+                Some(Action::Quit) => return false,
+                _ => {}
             }
+        }
+    }
 
-            // Add a newline character at the end of the line
-            temp_buffer[idx] = b'\n';
-            idx += 1;
+    /// Shows the boards as the viewer sees them while the other side is
+    /// busy.
+    pub fn display_waiting(&mut self, view: &PlayerView, text: &str) {
+        self.render_view(view);
+
+        let (ltitle, rtitle) = board_titles(view.player);
+        self.clear();
+        self.display_two_boards(fleets_seen_by(view), ltitle, rtitle);
+        self.frame.println("");
+        self.frame.println(text);
+        self.present();
+    }
 
-            // Print the line
-            stdout.write_all(&temp_buffer[..idx]).unwrap();
+    /// Lets the player aim and fire. The enemy shots since the player's
+    /// last turn are animated first and stay highlighted on the player's
+    /// own board. The game in play is saved through `save`.
+    pub fn read_shoot(
+        &mut self,
+        view: &PlayerView,
+        incoming: &[(u128, Shot)],
+        save: &dyn Fn() -> io::Result<PathBuf>,
+    ) -> Result<u128, Interrupt> {
+        let player = view.player;
+        let mut crosshair: u128 = CELL;
+        let mut status = incoming
+            .iter()
+            .map(|&(shoot, shot)| describe_incoming_shot(shoot, shot))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.render_view(view);
+        for &(shoot, _) in incoming {
+            self.animate_shot(view, player.index(), shoot);
         }
+        self.highlights[player.index()] = incoming
+            .iter()
+            .fold(0, |highlight, &(shoot, _)| highlight | shoot);
 
-        stdout.flush().unwrap();
-    }
-
-    fn display_players_ships_status(&self, game: &Game) {
-        let mut stdout = io::stdout();
-
-        // Allocate the temporary buffer on the stack
-        let mut temp_buffer = [0; 2 * BOARD_SIZE * CELL_SIZE + 1 + 2]; // +1 for the newline character. +2 tab
-
-        // Display ships under board, line by line
-        for i in 0..SHIPS_COUNT  {
-            let mut idx = 0;
-            let alpha_ship = game.ships_alpha[i];
-            let alpha_ship_size = ship_size(alpha_ship);
-            let alpha_ship_damage = ship_size(alpha_ship & game.shoots_beta);
-            let alpha_ship_undamage = alpha_ship_size - alpha_ship_damage;
-
-            let beta_ship = game.ships_beta[i];
-            let beta_ship_size = ship_size(beta_ship);
-            let beta_ship_damage = ship_size(beta_ship & game.shoots_alpha);
-            let beta_ship_undamage = beta_ship_size - beta_ship_damage;
-
-            let alpha_chunks = [
-                alpha_ship_undamage,
-                alpha_ship_damage,
-                BOARD_SIZE - alpha_ship_size,
-            ];
-            let beta_chunks = [
-                beta_ship_undamage,
-                beta_ship_damage,
-                BOARD_SIZE - beta_ship_size,
-            ];
-            let cells = [&CELL_SHIP, &CELL_HIT, &CELL_MISS];
-
-            for (chunk, cell) in alpha_chunks.into_iter().zip(cells.into_iter()) {
-                for _ in 0..chunk {
-                    temp_buffer[idx..idx + CELL_SIZE].copy_from_slice(cell);
-                    idx += CELL_SIZE;
+        loop {
+            self.clear();
+            let enemy = match player {
+                Player::Alpha => &mut self.rbuffer,
+                Player::Beta => &mut self.lbuffer,
+            };
+            render_enemy_player_board(enemy, view);
+            render(enemy, crosshair, CellKind::Crosshair);
+
+            let (ltitle, rtitle) = board_titles(player);
+            self.display_two_boards(fleets_seen_by(view), ltitle, rtitle);
+            self.frame.println("");
+            self.frame.println(&status);
+
+            let key = self.getkey();
+            let mut fire = false;
+
+            if let Key::Click { column, row } = key {
+                let enemy_board = self.board_cells[player.other().index()];
+                if let Some(cell) = board_cell_at(column, row, enemy_board) {
+                    crosshair = cell;
+                    fire = true;
                 }
             }
 
-            // Add a tab characters at the end of the line
-            temp_buffer[idx] = b'\t';
-            idx += 1;
-            temp_buffer[idx] = b'\t';
-            idx += 1;
+            match self.keys.action(key) {
+                _ if fire => {}
+                Some(Action::Confirm) => fire = true,
+                Some(Action::Move(direction)) => crosshair = saturated_move(crosshair, direction),
+                Some(Action::Jump(direction)) => crosshair = jump(crosshair, direction),
+                Some(Action::Save) => status = self.save(save),
+                Some(Action::Help) => self.show_help(),
+                Some(Action::Quit) => self.pause_menu(save)?,
+                _ => {}
+            }
 
-            for (chunk, cell) in beta_chunks.into_iter().zip(cells.into_iter()) {
-                for _ in 0..chunk {
-                    temp_buffer[idx..idx + CELL_SIZE].copy_from_slice(cell);
-                    idx += CELL_SIZE;
+            if fire {
+                match target(view, crosshair) {
+                    Ok(cell) => return Ok(cell),
+                    Err(text) => status = text,
                 }
             }
+        }
+    }
 
-            temp_buffer[idx] = b'\n';
-            idx += 1;
+    /// Lets the player place the whole fleet, starting from the ships
+    /// already placed, and returns it. Ships are dropped with the confirm
+    /// key, and the next-ship key picks up a placed ship again, so the
+    /// layout can be changed until the whole fleet is confirmed. `save`
+    /// saves the game with the fleet as far as it is placed.
+    pub fn read_fleet(
+        &mut self,
+        rules: Rules,
+        ships: [u128; SHIPS_COUNT],
+        save: &dyn Fn(&[u128; SHIPS_COUNT]) -> io::Result<PathBuf>,
+    ) -> Result<[u128; SHIPS_COUNT], Interrupt> {
+        // Only the fleet being placed, so the rules check every ship
+        let player = Player::Alpha;
+        let mut game = Game {
+            rules,
+            ..Game::default()
+        };
+        // A layout that breaks the rules starts over from an empty board
+        let _ = game.place_partial_fleet(player, ships);
+        // Layer and position of the ship the player is currently moving
+        let mut held = next_unplaced_ship(&game, player);
+        // Where the held ship was picked up from, if it was on the board
+        let mut lifted = None;
+        // Layers in the order they were placed, for undo
+        let mut placed: Vec<usize> = Vec::new();
+        let mut status = String::new();
 
-            // Print the line
-            stdout.write_all(&temp_buffer[..idx]).unwrap();
-        }
+        loop {
+            self.clear();
+            let ships = game.get_ships(player);
+            let board = ships.iter().fold(0, |board, ship| board | ship);
 
-        stdout.flush().unwrap();
-    }
-    pub fn display_scene_after_shoot(&mut self, game: &Game, player: Player) {
-        let lbuffer = &mut self.lbuffer;
-        let rbuffer = &mut self.rbuffer;
-        clear();
-        render_unknown(lbuffer);
-        render_unknown(rbuffer);
-
-        if player == Player::Alpha {
-            render_current_player_board(lbuffer, game, Player::Alpha);
-            render_enemy_player_board(rbuffer, game, Player::Alpha);
-        } else {
-            render_current_player_board(rbuffer, game, Player::Beta);
-            render_enemy_player_board(lbuffer, game, Player::Beta);
+            render_unknown(&mut self.lbuffer);
+            match held {
+                Some((_, ship)) => render_board_ships_n_new_ship(&mut self.lbuffer, board, ship),
+                None => render(&mut self.lbuffer, board, CellKind::Ship),
+            }
+            self.display_left_board("Your fleet");
+            self.frame.println("");
+
+            let hint = match held {
+                Some((layer, _)) => format!(
+                    "Ship {} of {} (size {}). {}: place, {}: rotate, {}: pick up next ship",
+                    layer + 1,
+                    SHIPS_COUNT,
+                    SHIP_SIZES[layer],
+                    self.keys.describe(Action::Confirm),
+                    self.keys.describe(Action::Rotate),
+                    self.keys.describe(Action::Next),
+                ),
+                None => format!(
+                    "Fleet is ready. {}: confirm, {}: pick up a ship",
+                    self.keys.describe(Action::Confirm),
+                    self.keys.describe(Action::Next),
+                ),
+            };
+            self.frame.println(&hint);
+            self.frame.println(&format!(
+                "{}: random layout, {}: undo, {}: help, {}: menu",
+                self.keys.describe(Action::Randomize),
+                self.keys.describe(Action::Undo),
+                self.keys.describe(Action::Help),
+                self.keys.describe(Action::Quit),
+            ));
+            self.frame.println(&status);
+
+            let key = self.getkey();
+            let Some(action) = self.keys.action(key) else {
+                continue;
+            };
+
+            match (held, action) {
+                (None, Action::Confirm) => return Ok(ships),
+                (Some((layer, ship)), Action::Confirm) => {
+                    if game.add_ship(player, ship, layer).is_err() {
+                        continue;
+                    }
+                    placed.push(layer);
+                    held = next_unplaced_ship(&game, player);
+                    lifted = None;
+                }
+                (_, Action::Next) => {
+                    if let Some((layer, ship)) = held {
+                        if game.add_ship(player, ship, layer).is_err() {
+                            continue;
+                        }
+                        placed.push(layer);
+                    }
+
+                    let last = placed.last().copied().unwrap_or(0);
+                    let layer = (1..=SHIPS_COUNT)
+                        .map(|i| (last + i) % SHIPS_COUNT)
+                        .find(|&i| game.get_ships(player)[i] != 0);
+
+                    if let Some(layer) = layer {
+                        placed.retain(|&i| i != layer);
+                        let ship = game.remove_ship(player, layer);
+                        held = Some((layer, ship));
+                        lifted = Some(ship);
+                    }
+                }
+                (_, Action::Undo) => {
+                    (held, lifted) = undo(&mut game, player, &mut placed, held, lifted);
+                }
+                (_, Action::Randomize) => {
+                    game.place_random_fleet(player, &mut self.rng);
+                    placed = (0..SHIPS_COUNT).collect();
+                    held = None;
+                    lifted = None;
+                }
+                (Some((layer, ship)), Action::Rotate) => held = Some((layer, rotate(ship))),
+                (Some((layer, ship)), Action::Move(direction)) => {
+                    held = Some((layer, saturated_move(ship, direction)))
+                }
+                (Some((layer, ship)), Action::Jump(direction)) => {
+                    held = Some((layer, jump(ship, direction)))
+                }
+                (_, Action::Save) => status = self.save(&|| save(&ships)),
+                (_, Action::Help) => self.show_help(),
+                (_, Action::Quit) => self.pause_menu(&|| save(&ships))?,
+                _ => {}
+            }
         }
+    }
 
-        self.display_two_boards();
-        println!();
-        self.display_players_ships_status(game);
-        println!();
-
-        wait_for_enter("");
+    fn save(&self, save: &dyn Fn() -> io::Result<PathBuf>) -> String {
+        match self.saving_disabled {
+            true => String::from("Network games cannot be saved"),
+            false => save_status(save()),
+        }
     }
 
-    pub fn display_last_scene(&mut self, game: &Game) {
-        let lbuffer = &mut self.lbuffer;
-        let rbuffer = &mut self.rbuffer;
+    /// Shows the pause menu until the player resumes the game. Conceding or
+    /// quitting is passed to the caller as an error.
+    fn pause_menu(&mut self, save: &dyn Fn() -> io::Result<PathBuf>) -> Result<(), Interrupt> {
+        const ITEMS: [&str; 4] = ["Resume", "Save", "Concede", "Quit"];
 
-        clear();
-        render_unknown(lbuffer);
-        render_unknown(rbuffer);
-        render_current_player_board(lbuffer, game, Player::Alpha);
-        render_current_player_board(rbuffer, game, Player::Beta);
-        self.display_two_boards();
-        println!();
-        self.display_players_ships_status(game);
-        println!();
+        let mut selected = 0;
+        let mut status = String::new();
 
-        match game.get_winner() {
-            Some(Player::Alpha) => wait_for_enter("Player Alpha wins!"),
-            Some(Player::Beta) => wait_for_enter("Player Beta wins!"),
-            _ => panic!("Invalid winner."),
+        loop {
+            self.clear();
+            self.frame.println("Game paused");
+            self.frame.println("");
+            for (i, item) in ITEMS.iter().enumerate() {
+                let marker = if i == selected { '>' } else { ' ' };
+                self.frame.println(&format!(" {} {}", marker, item));
+            }
+            self.frame.println("");
+            self.frame.println(&status);
+
+            let key = self.getkey();
+            match self.keys.action(key) {
+                Some(Action::Move(Direction::Up)) => selected = selected.saturating_sub(1),
+                Some(Action::Move(Direction::Down)) => {
+                    selected = (selected + 1).min(ITEMS.len() - 1)
+                }
+                Some(Action::Quit) => return Ok(()),
+                Some(Action::Save) => status = self.save(save),
+                Some(Action::Help) => self.show_help(),
+                Some(Action::Confirm) => match ITEMS[selected] {
+                    "Save" => status = self.save(save),
+                    "Concede" => return Err(Interrupt::Concede),
+                    "Quit" => return Err(Interrupt::Quit),
+                    _ => return Ok(()),
+                },
+                _ => {}
+            }
         }
     }
 
-    pub fn read_shoot(&mut self, game: &Game, player: Player) -> u128 {
-        let mut crosshair: u128 = CELL;
+    /// Lists the key bindings and what the board cells mean.
+    fn show_help(&mut self) {
+        self.until_enter(IO::display_help);
+    }
 
+    fn display_help(&mut self) {
+        self.frame.println("Controls");
+        self.frame.println("");
+        for action in ACTIONS {
+            let line = format!("  {:<10} {}", action.name(), self.keys.describe(action));
+            self.frame.println(&line);
+        }
+        self.frame
+            .println(&format!("  {:<10} click a cell on the enemy board", "fire"));
+        self.frame.println("");
+        self.frame.println("Cells");
+        self.frame.println("");
+
+        for kind in CELL_KINDS {
+            let row = self.frame.line();
+            let column = self.frame.glyphs(2, row, &self.theme.cell(kind).glyphs());
+            self.frame
+                .text(column + 1, row, kind.description(), Style::default());
+            self.frame.set_line(row + 1);
+        }
+        self.frame.println("");
+    }
+
+    #[allow(dead_code)]
+    pub fn print_mask(&mut self, mask: u128) {
         render_unknown(&mut self.lbuffer);
-        render_unknown(&mut self.rbuffer);
+        render(&mut self.lbuffer, mask, CellKind::Ship);
+        self.display_left_board("");
+        self.present();
+    }
+
+    /// Starts drawing a new screen. Nothing reaches the terminal until the
+    /// screen is presented, and then only what changed.
+    pub fn clear(&mut self) {
+        self.frame = Frame::default();
+    }
 
-        if player == Player::Alpha {
-            render_current_player_board(&mut self.lbuffer, game, Player::Alpha);
-        } else {
-            render_current_player_board(&mut self.rbuffer, game, Player::Beta);
+    /// Shows the screen drawn so far, or asks for a bigger terminal if it
+    /// does not fit.
+    fn present(&mut self) {
+        let frame = match term::terminal_size() {
+            Some((columns, rows)) if self.frame.width() > columns || self.frame.height() > rows => {
+                too_small_frame(&self.frame, columns)
+            }
+            _ => self.frame.clone(),
+        };
+        self.renderer.present(frame);
+    }
+
+    /// Shows the screen drawn so far and waits for a key. After a resize
+    /// the whole screen is drawn again.
+    fn getkey(&mut self) -> Key {
+        self.present();
+        let key = read_key();
+
+        if key == Key::Resize {
+            self.renderer.invalidate();
         }
 
+        key
+    }
+
+    /// Draws a screen until Enter is pressed. It is drawn again after every
+    /// key, so it follows the terminal size.
+    fn until_enter(&mut self, draw: impl Fn(&mut IO)) {
         loop {
-            clear();
-            if player == Player::Alpha {
-                render_enemy_player_board(&mut self.rbuffer, game, Player::Alpha);
-                render(&mut self.rbuffer, crosshair, CELL_CROSSHAIR);
-            } else {
-                render_enemy_player_board(&mut self.lbuffer, game, Player::Beta);
-                render(&mut self.lbuffer, crosshair, CELL_CROSSHAIR);
+            self.clear();
+            draw(self);
+            self.frame.println("Press enter to continue...");
+            if self.getkey() == Key::Enter {
+                return;
             }
-            self.display_two_boards();
-            println!();
-            self.display_players_ships_status(game);
+        }
+    }
 
-            let input = getchar();
+    pub fn wait_for_enter(&mut self, text: &str) {
+        self.frame.println(text);
+        self.frame.println("Press enter to continue...");
+        while self.getkey() != Key::Enter {}
+    }
 
-            if input == '\n' {
-                break;
-            }
+    /// Asks a yes or no question, Enter means yes.
+    pub fn ask(&mut self, question: &str) -> bool {
+        self.clear();
+        self.frame.println(&format!("{} [Y/n]", question));
 
-            crosshair = move_by_user_input(crosshair, input);
+        loop {
+            match self.getkey() {
+                Key::Enter | Key::Char('y' | 'Y') => return true,
+                Key::Esc | Key::Char('n' | 'N') => return false,
+                _ => {}
+            }
         }
-
-        crosshair
     }
+}
 
-    pub fn read_new_ship(&mut self, game: &Game, player: Player, ship_size: usize) -> u128 {
-        let mut new_ship = create_ship(ship_size);
+fn save_status(saved: io::Result<PathBuf>) -> String {
+    match saved {
+        Ok(path) => format!("Game saved to {}", path.display()),
+        Err(err) => format!("Cannot save the game: {}", err),
+    }
+}
 
-        render_unknown(&mut self.lbuffer);
+/// Returns the first empty layer together with a fresh ship of its size.
+/// Takes back the last placed ship, `placed` being the layers in the
+/// order they were placed. A held ship that was picked up from the board
+/// goes back to `lifted` first, so it is not lost. Returns the ship to hold
+/// and where it was picked up from.
+fn undo(
+    game: &mut Game,
+    player: Player,
+    placed: &mut Vec<usize>,
+    held: Option<(usize, u128)>,
+    lifted: Option<u128>,
+) -> (Option<(usize, u128)>, Option<u128>) {
+    if let (Some((layer, _)), Some(ship)) = (held, lifted) {
+        game.add_ship(player, ship, layer)
+            .expect("nothing moved since the ship was picked up");
+    }
 
-        loop {
-            clear();
-            let board = game.get_board(player);
+    match (placed.pop(), lifted) {
+        (Some(layer), _) => {
+            let ship = game.remove_ship(player, layer);
+            (Some((layer, ship)), Some(ship))
+        }
+        (None, Some(_)) => (next_unplaced_ship(game, player), None),
+        (None, None) => (held, None),
+    }
+}
 
-            render_board_ships_n_new_ship(&mut self.lbuffer, board, new_ship);
-            self.display_left_board();
+fn next_unplaced_ship(game: &Game, player: Player) -> Option<(usize, u128)> {
+    let ships = game.get_ships(player);
+    let layer = (0..SHIPS_COUNT).find(|&i| ships[i] == 0)?;
 
-            let input = getchar();
+    Some((layer, create_ship(SHIP_SIZES[layer])))
+}
 
-            render_unknown(&mut self.lbuffer);
+fn render_unknown(buffer: &mut OutputBuffer) {
+    *buffer = [[CellKind::Unknown; BOARD_SIZE]; BOARD_SIZE];
+}
 
-            if input == '\n' && game.can_place_ship(player, new_ship) {
-                break;
+fn render(buffer: &mut OutputBuffer, mask: u128, cell: CellKind) {
+    for (y, row) in buffer.iter_mut().enumerate() {
+        for (x, slot) in row.iter_mut().enumerate() {
+            if board_get(mask, x, y) {
+                *slot = cell;
             }
+        }
+    }
+}
 
-            if input == 'f' {
-                new_ship = transpose(new_ship);
-            }
+fn render_board_ships_n_new_ship(buffer: &mut OutputBuffer, board: u128, new_ship: u128) {
+    let collision = new_ship & create_surround_mask(board);
 
-            new_ship = move_by_user_input(new_ship, input);
-        }
+    render(buffer, board, CellKind::Ship);
+    render(buffer, new_ship, CellKind::NewShip);
+    render(buffer, collision, CellKind::Collision);
+}
+
+fn render_current_player_board(buffer: &mut OutputBuffer, view: &PlayerView) {
+    let board = view.board();
+    let other_shoots = view.incoming;
+    let hits: u128 = other_shoots & board;
+
+    render_unknown(buffer);
+    render(buffer, board, CellKind::Ship);
+    render(buffer, other_shoots, CellKind::Miss);
+    render(buffer, hits, CellKind::Hit);
+}
+
+fn render_enemy_player_board(buffer: &mut OutputBuffer, view: &PlayerView) {
+    render_unknown(buffer);
+    render(buffer, view.shots, CellKind::Miss);
+    render(buffer, view.hits, CellKind::Hit);
+}
 
-        new_ship
+/// Reads the next key. Ctrl-C quits the game right away.
+fn read_key() -> Key {
+    let key = term::read_key();
+
+    if key == Key::CtrlC {
+        term::interrupt();
     }
 
-    #[allow(dead_code)]
-    pub fn print_mask(&mut self, mask: u128) {
-        render_unknown(&mut self.lbuffer);
-        render(&mut self.lbuffer, mask, CELL_SHIP);
-        self.display_left_board();
+    key
+}
+
+/// Moves a ship or crosshair as far as possible in the given direction.
+fn jump(board: u128, direction: Direction) -> u128 {
+    let mut board = board;
+    loop {
+        let moved = saturated_move(board, direction);
+        if moved == board {
+            return board;
+        }
+        board = moved;
     }
 }
 
-#[inline(always)]
-fn copy_cell(cell: &Cell, buffer: &mut OutputBuffer, x: usize, y: usize) {
-    buffer[y][x][..CELL_SIZE].copy_from_slice(cell)
+/// Width of the `10 ` row labels left of every board.
+const ROW_LABEL_WIDTH: usize = 3;
+const BOARD_WIDTH: usize = ROW_LABEL_WIDTH + BOARD_SIZE * CELL_WIDTH;
+/// Spaces between two boards printed side by side.
+const BOARD_GAP: usize = 6;
+/// Lines above the first row of cells: the title and the column labels.
+const BOARD_HEADER_LINES: usize = 2;
+
+/// Lines of ship status under each board, after an empty line.
+const STATUS_LINES: usize = SHIPS_COUNT + 1;
+
+/// How several boards are arranged on the screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Layout {
+    SideBySide,
+    /// One under the other, for narrow terminals.
+    Stacked,
 }
 
-fn render_unknown(buffer: &mut OutputBuffer) {
-    for y in 0..BOARD_SIZE {
-        for x in 0..BOARD_SIZE {
-            copy_cell(&CELL_UNKNOWN, buffer, x, y);
+impl Layout {
+    fn for_width(width: Option<usize>) -> Layout {
+        match width {
+            Some(width) if width < 2 * BOARD_WIDTH + BOARD_GAP => Layout::Stacked,
+            _ => Layout::SideBySide,
         }
     }
 }
 
-fn render(buffer: &mut OutputBuffer, mask: u128, cell: Cell) {
-    for y in 0..BOARD_SIZE {
+fn terminal_width() -> Option<usize> {
+    term::terminal_size().map(|(columns, _)| columns)
+}
+
+/// Draws boards, each with a title above it and A-J, 1-10 labels around
+/// it and the highlighted cells framed, leaving `footer_lines` empty lines
+/// under every board. Boards are
+/// placed at fixed columns rather than separated by tabs, so the layout
+/// does not depend on the tab width. Returns the terminal cell of A1 of
+/// every board.
+fn display_boards(
+    frame: &mut Frame,
+    theme: &Theme,
+    layout: Layout,
+    boards: &[(&OutputBuffer, u128, &str)],
+    footer_lines: usize,
+) -> Vec<(usize, usize)> {
+    let first_top = frame.line();
+    let board_height = BOARD_HEADER_LINES + BOARD_SIZE + footer_lines;
+    let mut cells = Vec::new();
+
+    for (i, &(buffer, highlight, title)) in boards.iter().enumerate() {
+        let (left, top) = match layout {
+            Layout::SideBySide => (i * (BOARD_WIDTH + BOARD_GAP), first_top),
+            Layout::Stacked => (0, first_top + i * (board_height + 1)),
+        };
+        cells.push((left + ROW_LABEL_WIDTH, top + BOARD_HEADER_LINES));
+
+        frame.text(left, top, title, Style::default());
         for x in 0..BOARD_SIZE {
-            if board_get(mask, x, y) {
-                copy_cell(&cell, buffer, x, y);
+            let label = (b'A' + x as u8) as char;
+            let column = left + ROW_LABEL_WIDTH + x * CELL_WIDTH + 1;
+            frame.text(column, top + 1, &label.to_string(), Style::default());
+        }
+
+        for (y, line) in buffer.iter().enumerate() {
+            let row = top + BOARD_HEADER_LINES + y;
+            let label = format!("{:>2} ", y + 1);
+            let mut column = frame.text(left, row, &label, Style::default());
+            for (x, &cell) in line.iter().enumerate() {
+                let styled = match board_get(highlight, x, y) {
+                    true => theme.highlighted(cell),
+                    false => theme.cell(cell),
+                };
+                column = frame.glyphs(column, row, &styled.glyphs());
             }
         }
+
+        frame.set_line(top + board_height);
     }
-}
 
-fn render_board_ships_n_new_ship(buffer: &mut OutputBuffer, board: u128, new_ship: u128) {
-    let collision = new_ship & create_surround_mask(board);
+    cells
+}
 
-    render(buffer, board, CELL_SHIP);
-    render(buffer, new_ship, CELL_NEW_SHIP);
-    render(buffer, collision, CELL_COLLISION);
+/// Replaces a frame that does not fit into the terminal with a message
+/// saying how big it has to be.
+fn too_small_frame(frame: &Frame, columns: usize) -> Frame {
+    let text = format!(
+        "Terminal too small, make it at least {}x{}.",
+        frame.width(),
+        frame.height()
+    );
+
+    let mut small = Frame::default();
+    for line in text.as_bytes().chunks(columns.max(1)) {
+        small.println(std::str::from_utf8(line).unwrap());
+    }
+    small
 }
 
-fn render_current_player_board(buffer: &mut OutputBuffer, game: &Game, player: Player) {
-    let board = game.get_board(player);
-    let other_shoots = game.get_shoots(player.other());
-    let hits: u128 = other_shoots & board;
+/// Widest line of text, as wide as two boards side by side.
+const TEXT_WIDTH: usize = 2 * BOARD_WIDTH + BOARD_GAP;
+
+/// Breaks text into lines of at most `width` characters at spaces. A word
+/// longer than a line gets a line of its own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
 
-    render_unknown(buffer);
-    render(buffer, board, CELL_SHIP);
-    render(buffer, other_shoots, CELL_MISS);
-    render(buffer, hits, CELL_HIT);
+/// Frames of the shot animation, each shown for `ANIMATION_FRAME`.
+const SHOT_ANIMATION: [CellKind; 4] = [
+    CellKind::Crosshair,
+    CellKind::Incoming,
+    CellKind::Crosshair,
+    CellKind::Incoming,
+];
+const ANIMATION_FRAME: Duration = Duration::from_millis(120);
+
+/// Status of Alpha's and Beta's fleet as the viewer knows them.
+fn fleets_seen_by(view: &PlayerView) -> [Fleet; 2] {
+    match view.player {
+        Player::Alpha => [view.own_fleet(), view.enemy_fleet()],
+        Player::Beta => [view.enemy_fleet(), view.own_fleet()],
+    }
 }
 
-fn render_enemy_player_board(buffer: &mut OutputBuffer, game: &Game, player: Player) {
-    let other_player_board = game.get_board(player.other());
-    let shoots = game.get_shoots(player);
-    let hits: u128 = shoots & other_player_board;
+fn describe_own_shot(shoot: u128, shot: Shot) -> String {
+    let coord = Coord::from_board(shoot).expect("a shot hits one cell");
+    match shot {
+        Shot::Miss => format!("{}: miss.", coord),
+        Shot::Hit => format!("{}: hit!", coord),
+        Shot::Sunk(layer) => format!("{}: you sank their {}!", coord, SHIP_NAMES[layer]),
+    }
+}
 
-    render_unknown(buffer);
-    render(buffer, shoots, CELL_MISS);
-    render(buffer, hits, CELL_HIT);
+fn describe_incoming_shot(shoot: u128, shot: Shot) -> String {
+    let coord = Coord::from_board(shoot).expect("a shot hits one cell");
+    match shot {
+        Shot::Miss => format!("Enemy fired at {} and missed.", coord),
+        Shot::Hit => format!("Enemy fired at {} and hit!", coord),
+        Shot::Sunk(layer) => format!(
+            "Enemy fired at {} and sank your {}!",
+            coord, SHIP_NAMES[layer]
+        ),
+    }
 }
 
-pub fn clear() {
-    print!("{}[2J{}[1;1H", 27 as char, 27 as char);
+/// Titles of the left (Alpha's) and right (Beta's) boards as seen by the
+/// player whose turn it is.
+fn board_titles(player: Player) -> (&'static str, &'static str) {
+    match player {
+        Player::Alpha => ("Your fleet", "Enemy waters"),
+        Player::Beta => ("Enemy waters", "Your fleet"),
+    }
 }
 
-pub fn wait_for_enter(text: &str) {
-    println!("{}", text);
-    println!("Press enter to continue...");
-    while getchar() != '\n' {}
+/// Converts a clicked terminal cell to a board cell, if it hits the board
+/// whose A1 is at the given terminal cell.
+/// The cell to fire at, or why it cannot be: the player already knows
+/// what is there.
+fn target(view: &PlayerView, cell: u128) -> Result<u128, String> {
+    match view.unknown() & cell {
+        0 => {
+            let coord = Coord::from_board(cell).expect("the crosshair is one cell");
+            Err(format!("{} is already known, pick another cell", coord))
+        }
+        _ => Ok(cell),
+    }
 }
 
-fn getchar() -> char {
-    let mut termios = Termios::from_fd(0).unwrap();
-    termios.c_lflag &= !(ECHO | ICANON);
-    termios.c_cc[VMIN] = 1;
-    termios.c_cc[VTIME] = 0;
-    tcsetattr(0, TCSANOW, &termios).unwrap();
-    let mut buf = [0u8; 1];
-    io::stdin().read_exact(&mut buf).unwrap();
-    termios.c_lflag |= ECHO | ICANON;
-    tcsetattr(0, TCSANOW, &termios).unwrap();
-    buf[0] as char
+fn board_cell_at(column: usize, row: usize, board: (usize, usize)) -> Option<u128> {
+    let x = column.checked_sub(board.0)? / CELL_WIDTH;
+    let y = row.checked_sub(board.1)?;
+
+    if x >= BOARD_SIZE || y >= BOARD_SIZE {
+        return None;
+    }
+
+    Some(board_set(0, x, y, true))
 }
 
-// will be reused for shooting
-// you are free to rename it as you want
-fn move_by_user_input(board: u128, input: char) -> u128 {
-    match input {
-        'k' | 'w' => saturated_move(board, Direction::Up),
-        'j' | 's' => saturated_move(board, Direction::Down),
-        'h' | 'a' => saturated_move(board, Direction::Left),
-        'l' | 'd' => saturated_move(board, Direction::Right),
-        _ => board,
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::game::Game;
+
+    #[test]
+    fn known_cells_are_not_targets() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(6));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(7));
+        let ship = game.get_ships(Player::Beta)[4];
+        for coord in Coord::all().filter(|coord| ship & coord.to_board() != 0) {
+            game.shoot(Player::Alpha, coord.to_board()).unwrap();
+        }
+        if game.get_shoots(Player::Alpha) & CELL == 0 {
+            game.shoot(Player::Alpha, CELL).unwrap();
+        }
+
+        let view = PlayerView::new(&game, Player::Alpha);
+        for cell in Coord::all().map(Coord::to_board) {
+            match target(&view, cell) {
+                Ok(target) => assert_eq!(target & view.shots, 0),
+                Err(text) => {
+                    assert_ne!(cell & view.shots, 0);
+                    assert!(text.ends_with("is already known, pick another cell"));
+                }
+            }
+        }
+        assert_eq!(
+            target(&view, CELL),
+            Err(String::from("A1 is already known, pick another cell"))
+        );
+    }
+
+    #[test]
+    fn undo_keeps_picked_up_ships() {
+        let player = Player::Alpha;
+        let mut game = Game::default();
+        game.place_random_fleet(player, &mut Rng::new(2));
+        let fleet = game.get_ships(player);
+        let mut placed: Vec<usize> = (0..SHIPS_COUNT).collect();
+
+        // Undo twice picks up the last ship and then the one before it
+        let (held, lifted) = undo(&mut game, player, &mut placed, None, None);
+        assert_eq!(held, Some((4, fleet[4])));
+        let moved = held.map(|(layer, ship)| (layer, saturated_move(ship, Direction::Up)));
+        let (held, lifted) = undo(&mut game, player, &mut placed, moved, lifted);
+        assert_eq!(held, Some((3, fleet[3])));
+        assert_eq!(game.get_ships(player)[4], fleet[4]);
+
+        // With nothing left to take back the held ship still goes back
+        placed.clear();
+        let (held, lifted) = undo(&mut game, player, &mut placed, held, lifted);
+        assert_eq!(game.get_ships(player), fleet);
+        assert_eq!((held, lifted), (None, None));
+
+        // A ship that was never placed has nowhere to go back to
+        game.remove_ship(player, 0);
+        let fresh = next_unplaced_ship(&game, player);
+        assert_eq!(
+            undo(&mut game, player, &mut placed, fresh, None),
+            (fresh, None)
+        );
     }
 }
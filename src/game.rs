@@ -1,19 +1,60 @@
-use crate::board_api::create_surround_mask;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::board_api::{create_ship, create_surround_mask, transpose, wrapping_move, Direction};
+use crate::constants::BOARD_SIZE;
+use crate::coord::Coord;
+use crate::log::{Move, MoveLog};
+use crate::rng::Rng;
+use crate::rules::Rules;
 
 pub const SHIP_SIZES: [usize; 5] = [5, 4, 3, 3, 2];
 pub const SHIPS_COUNT: usize = SHIP_SIZES.len();
+pub const SHIP_NAMES: [&str; SHIPS_COUNT] = [
+    "carrier",
+    "battleship",
+    "destroyer",
+    "submarine",
+    "patrol boat",
+];
+
+/// Name of the ship on the layer for the start of a sentence or a label.
+#[cfg(feature = "std")]
+pub fn ship_title(layer: usize) -> String {
+    let mut chars = SHIP_NAMES[layer].chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
 
 // • авианосец - 5 ячеек(клеток);
 // • крейсер - 4 ячейки;
 // • разрушитель - 3 ячейки;
 // • подводная лодка - 3 ячейки;
 // • катер - 2 ячейки.
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Game {
     pub ships_alpha: [u128; SHIPS_COUNT],
     pub ships_beta: [u128; SHIPS_COUNT],
     pub shoots_alpha: u128,
     pub shoots_beta: u128,
+    pub conceded: Option<Player>,
+    pub rules: Rules,
+    pub moves: MoveLog,
+    /// Who shoots next and how many shots are left in their turn, kept up
+    /// to date by `shoot`. Whoever fired first started, and every turn
+    /// has `shots_per_turn` shots. `None` before the first shot.
+    pub turn: Option<(Player, usize)>,
+}
+
+/// What a shot did to the enemy fleet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shot {
+    Miss,
+    Hit,
+    /// The shot hit the last afloat cell of the ship on this layer.
+    Sunk(usize),
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -22,6 +63,62 @@ pub enum Player {
     Beta,
 }
 
+/// Both players, in the order of [`Player::index`].
+pub const PLAYERS: [Player; 2] = [Player::Alpha, Player::Beta];
+
+/// Why a ship cannot be placed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    /// Another ship is in the way, or too close by the rules.
+    Blocked,
+    /// The ship is not a straight line of its size.
+    Shape,
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::Blocked => write!(f, "another ship is in the way"),
+            PlacementError::Shape => write!(f, "ships are straight lines of their size"),
+        }
+    }
+}
+
+impl core::error::Error for PlacementError {}
+
+/// Why a move read from a file or the network could not have been played.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// A fleet is not complete yet.
+    NoFleet,
+    GameOver,
+    /// The other player is still shooting.
+    OutOfTurn,
+    /// The player already shot the cell, or it was revealed around a sunk
+    /// ship.
+    AlreadyShot,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoFleet => write!(f, "both fleets are needed before the shots"),
+            MoveError::GameOver => write!(f, "the game was already over"),
+            MoveError::OutOfTurn => write!(f, "it was the other player's turn"),
+            MoveError::AlreadyShot => write!(f, "the cell was already shot or revealed"),
+        }
+    }
+}
+
+impl core::error::Error for MoveError {}
+
+/// Why a player left the game in the middle of a turn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    Concede,
+    Quit,
+}
+
 impl Player {
     pub fn other(&self) -> Player {
         match self {
@@ -29,16 +126,73 @@ impl Player {
             Player::Beta => Player::Alpha,
         }
     }
+
+    /// Position in arrays that hold something for each player, Alpha
+    /// first.
+    pub const fn index(self) -> usize {
+        match self {
+            Player::Alpha => 0,
+            Player::Beta => 1,
+        }
+    }
+
+    pub const fn from_index(index: usize) -> Option<Player> {
+        match index {
+            0 => Some(Player::Alpha),
+            1 => Some(Player::Beta),
+            _ => None,
+        }
+    }
+
+    /// Name in the file formats, `alpha` or `beta`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Player::Alpha => "alpha",
+            Player::Beta => "beta",
+        }
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParsePlayerError;
+
+impl fmt::Display for ParsePlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected alpha or beta")
+    }
+}
+
+/// Parses `alpha` or `beta`.
+impl FromStr for Player {
+    type Err = ParsePlayerError;
+
+    fn from_str(text: &str) -> Result<Player, ParsePlayerError> {
+        PLAYERS
+            .into_iter()
+            .find(|player| player.name() == text)
+            .ok_or(ParsePlayerError)
+    }
 }
 
 impl Game {
-    pub fn get_board(&self, player: Player) -> u128 {
-        let ships = match player {
+    pub fn get_ships(&self, player: Player) -> [u128; SHIPS_COUNT] {
+        match player {
             Player::Alpha => self.ships_alpha,
             Player::Beta => self.ships_beta,
-        };
+        }
+    }
 
-        ships.into_iter().reduce(|acc, ship| acc | ship).unwrap_or(0)
+    pub fn get_board(&self, player: Player) -> u128 {
+        self.get_ships(player)
+            .into_iter()
+            .reduce(|acc, ship| acc | ship)
+            .unwrap_or(0)
     }
 
     pub fn get_shoots(&self, player: Player) -> u128 {
@@ -49,28 +203,83 @@ impl Game {
     }
 
     pub fn can_place_ship(&self, player: Player, ship: u128) -> bool {
-        let mask = create_surround_mask(ship);
+        let mask = match self.rules.touching {
+            true => ship,
+            false => create_surround_mask(ship),
+        };
         let board = self.get_board(player);
 
         mask & board == 0
     }
 
-    pub fn shoot(&mut self, player: Player, shoot: u128) {
-        let (player_shoots, mut layers) = match player {
-            Player::Alpha => (&mut self.shoots_alpha, self.ships_beta.into_iter()),
-            Player::Beta => (&mut self.shoots_beta, self.ships_alpha.into_iter()),
+    /// Fires at the cells of `shoot` and logs the moves. A cell the player
+    /// already shot or saw revealed is refused before anything changes,
+    /// so the log never holds a cell twice.
+    pub fn shoot(&mut self, player: Player, shoot: u128) -> Result<Shot, MoveError> {
+        if self.get_shoots(player) & shoot != 0 {
+            return Err(MoveError::AlreadyShot);
+        }
+        for cell in Coord::all().filter(|cell| shoot & cell.to_board() != 0) {
+            self.moves.push(Move { player, cell });
+        }
+
+        let turn = self.turn.unwrap_or((player, self.shots_per_turn(player)));
+        let shot = self.fire(player, shoot);
+        self.turn = Some(match turn {
+            (player, left) if left > 1 => (player, left - 1),
+            (player, _) => (player.other(), self.shots_per_turn(player.other())),
+        });
+        Ok(shot)
+    }
+
+    /// Marks the shot on the enemy board and tells what it did.
+    fn fire(&mut self, player: Player, shoot: u128) -> Shot {
+        let (player_shoots, layers) = match player {
+            Player::Alpha => (&mut self.shoots_alpha, self.ships_beta),
+            Player::Beta => (&mut self.shoots_beta, self.ships_alpha),
         };
 
         *player_shoots |= shoot;
 
         let shoots = *player_shoots;
 
-        if let Some(layer) = layers.find(move |layer| layer & !shoots == 0) {
-            *player_shoots |= create_surround_mask(layer);
+        if let Some(layer) = layers
+            .iter()
+            .position(|layer| layer & !shoots == 0 && layer & shoot != 0)
+        {
+            if self.rules.reveal_sunk {
+                *player_shoots |= create_surround_mask(layers[layer]);
+            }
+            return Shot::Sunk(layer);
+        }
+
+        if layers.iter().any(|layer| layer & shoot != 0) {
+            Shot::Hit
+        } else {
+            Shot::Miss
         }
     }
 
+    /// Fires a move that was not chosen on this board, like one read from
+    /// a file, if it could have been played.
+    pub fn play_move(&mut self, mv: Move) -> Result<Shot, MoveError> {
+        if !self.has_fleet(Player::Alpha) || !self.has_fleet(Player::Beta) {
+            return Err(MoveError::NoFleet);
+        }
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if self.turn.is_some_and(|(player, _)| player != mv.player) {
+            return Err(MoveError::OutOfTurn);
+        }
+
+        self.shoot(mv.player, mv.cell.to_board())
+    }
+
     pub fn get_winner(&self) -> Option<Player> {
+        if let Some(loser) = self.conceded {
+            return Some(loser.other());
+        }
         if self.get_board(Player::Beta) & !self.shoots_alpha == 0 {
             return Some(Player::Alpha);
         }
@@ -80,13 +289,66 @@ impl Game {
         None
     }
 
+    /// Whether every cell of the player's ship on the layer has been hit.
+    pub fn is_sunk(&self, player: Player, layer: usize) -> bool {
+        let ship = self.get_ships(player)[layer];
+        ship != 0 && ship & !self.get_shoots(player.other()) == 0
+    }
+
+    /// How many shots the player fires this turn.
+    pub fn shots_per_turn(&self, player: Player) -> usize {
+        match self.rules.salvo {
+            true => (0..SHIPS_COUNT)
+                .filter(|&layer| self.get_ships(player)[layer] != 0)
+                .filter(|&layer| !self.is_sunk(player, layer))
+                .count(),
+            false => 1,
+        }
+    }
+
     pub fn is_over(&self) -> bool {
         self.get_winner().is_some()
     }
 
-    pub fn add_ship(&mut self, player: Player, ship: u128, layer: usize) -> Result<(), ()> {
+    pub fn concede(&mut self, player: Player) {
+        self.conceded.get_or_insert(player);
+    }
+
+    /// The same fleets and rules before anybody fired.
+    pub fn without_shots(&self) -> Game {
+        Game {
+            ships_alpha: self.ships_alpha,
+            ships_beta: self.ships_beta,
+            rules: self.rules,
+            ..Game::default()
+        }
+    }
+
+    /// Fires the moves again from the start, yielding every move with its
+    /// result and the game right after it.
+    pub fn replay(&self) -> impl Iterator<Item = (Move, Shot, Game)> + '_ {
+        let mut game = self.without_shots();
+        self.moves.iter().map(move |mv| {
+            let shot = game
+                .shoot(mv.player, mv.cell.to_board())
+                .expect("the log holds every cell once");
+            (mv, shot, game)
+        })
+    }
+
+    /// Whether every ship of the player's fleet is on the board.
+    pub fn has_fleet(&self, player: Player) -> bool {
+        self.get_ships(player).into_iter().all(|ship| ship != 0)
+    }
+
+    pub fn add_ship(
+        &mut self,
+        player: Player,
+        ship: u128,
+        layer: usize,
+    ) -> Result<(), PlacementError> {
         if !self.can_place_ship(player, ship) {
-            return Err(());
+            return Err(PlacementError::Blocked);
         }
 
         match player {
@@ -96,13 +358,136 @@ impl Game {
 
         Ok(())
     }
+
+    /// Takes the ship off the given layer, leaving the layer empty.
+    /// Returns the removed ship, so it can be moved and placed again.
+    pub fn remove_ship(&mut self, player: Player, layer: usize) -> u128 {
+        let ships = match player {
+            Player::Alpha => &mut self.ships_alpha,
+            Player::Beta => &mut self.ships_beta,
+        };
+
+        core::mem::take(&mut ships[layer])
+    }
+
+    /// Replaces the player's fleet with the given one, if every ship is a
+    /// straight line of the right size and they are placed by the rules.
+    pub fn place_fleet(
+        &mut self,
+        player: Player,
+        ships: [u128; SHIPS_COUNT],
+    ) -> Result<(), PlacementError> {
+        if ships.contains(&0) {
+            return Err(PlacementError::Shape);
+        }
+        self.place_partial_fleet(player, ships)
+    }
+
+    /// Like `place_fleet`, but empty layers are ships not placed yet, as
+    /// in a game saved while placing.
+    pub fn place_partial_fleet(
+        &mut self,
+        player: Player,
+        ships: [u128; SHIPS_COUNT],
+    ) -> Result<(), PlacementError> {
+        let mut placed = Game {
+            rules: self.rules,
+            ..Game::default()
+        };
+        for (layer, ship) in ships.into_iter().enumerate() {
+            if ship == 0 {
+                continue;
+            }
+            if !is_ship_shape(ship, SHIP_SIZES[layer]) {
+                return Err(PlacementError::Shape);
+            }
+            placed.add_ship(player, ship, layer)?;
+        }
+
+        match player {
+            Player::Alpha => self.ships_alpha = ships,
+            Player::Beta => self.ships_beta = ships,
+        };
+        Ok(())
+    }
+
+    /// Replaces the player's fleet with a random valid layout.
+    pub fn place_random_fleet(&mut self, player: Player, rng: &mut Rng) {
+        'retry: loop {
+            for layer in 0..SHIPS_COUNT {
+                self.remove_ship(player, layer);
+            }
+
+            for (layer, size) in SHIP_SIZES.into_iter().enumerate() {
+                let mut attempts = 0;
+                while self
+                    .add_ship(player, random_ship(size, rng), layer)
+                    .is_err()
+                {
+                    attempts += 1;
+                    // Earlier ships can leave no room, so start over
+                    if attempts > 1000 {
+                        continue 'retry;
+                    }
+                }
+            }
+
+            return;
+        }
+    }
+}
+
+/// The ship of the size starting at the cell and going right, or down, if
+/// it fits on the board.
+pub fn ship_at(start: Coord, size: usize, down: bool) -> Option<u128> {
+    let (ship, width, height) = match down {
+        true => (transpose(create_ship(size)), 1, size),
+        false => (create_ship(size), size, 1),
+    };
+    if start.x + width > BOARD_SIZE || start.y + height > BOARD_SIZE {
+        return None;
+    }
+
+    let ship = wrapping_move(ship, start.x, Direction::Right);
+    Some(wrapping_move(ship, start.y, Direction::Down))
+}
+
+/// Whether the board holds exactly one ship of the size, across or down.
+pub fn is_ship_shape(ship: u128, size: usize) -> bool {
+    let Some(start) = Coord::from_board(ship) else {
+        return false;
+    };
+
+    [false, true]
+        .into_iter()
+        .any(|down| ship_at(start, size, down) == Some(ship))
+}
+
+fn random_ship(size: usize, rng: &mut Rng) -> u128 {
+    let (ship, width, height) = if rng.coin() {
+        (transpose(create_ship(size)), 1, size)
+    } else {
+        (create_ship(size), size, 1)
+    };
+
+    let ship = wrapping_move(ship, rng.below(BOARD_SIZE - width + 1), Direction::Right);
+    wrapping_move(ship, rng.below(BOARD_SIZE - height + 1), Direction::Down)
 }
 
 mod test {
     #![allow(unused_imports)]
 
     use super::*;
-    use crate::board_api::{create_ship, transpose, wrapping_move, Direction};
+    use crate::board_api::ship_size;
+    use crate::constants::CELL;
+
+    /// Ships at the start of every other row, the longest first.
+    #[allow(dead_code)]
+    fn fleet_in_rows() -> [u128; SHIPS_COUNT] {
+        core::array::from_fn(|layer| {
+            ship_at(Coord::new(0, 2 * layer), SHIP_SIZES[layer], false).unwrap()
+        })
+    }
 
     #[test]
     fn cant_place_a_ship() {
@@ -113,7 +498,10 @@ mod test {
         game.add_ship(Player::Alpha, ship, 0).unwrap();
         let ship = create_ship(3);
         assert!(!game.can_place_ship(Player::Alpha, ship));
-        assert_eq!(game.add_ship(Player::Alpha, ship, 0), Err(()));
+        assert_eq!(
+            game.add_ship(Player::Alpha, ship, 0),
+            Err(PlacementError::Blocked)
+        );
     }
 
     #[test]
@@ -141,4 +529,219 @@ mod test {
         assert!(game.can_place_ship(Player::Alpha, ship));
         assert_eq!(game.add_ship(Player::Alpha, ship, 2), Ok(()));
     }
+
+    #[test]
+    fn remove_and_place_again() {
+        let mut game = Game::default();
+        let ship = create_ship(4);
+        game.add_ship(Player::Alpha, ship, 1).unwrap();
+        let moved = wrapping_move(ship, 1, Direction::Down);
+        assert!(!game.can_place_ship(Player::Alpha, moved));
+        assert_eq!(game.remove_ship(Player::Alpha, 1), ship);
+        assert_eq!(game.get_board(Player::Alpha), 0);
+        assert_eq!(game.add_ship(Player::Alpha, moved, 1), Ok(()));
+        assert_eq!(game.get_ships(Player::Alpha)[1], moved);
+    }
+
+    #[test]
+    fn random_fleet_is_valid() {
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let mut game = Game::default();
+            game.place_random_fleet(Player::Beta, &mut rng);
+            let ships = game.get_ships(Player::Beta);
+            for (layer, ship) in ships.into_iter().enumerate() {
+                assert_eq!(ship_size(ship), SHIP_SIZES[layer]);
+                let others = ships
+                    .into_iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i != layer)
+                    .fold(0, |acc, (_, other)| acc | other);
+                assert_eq!(create_surround_mask(ship) & others, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn shot_results() {
+        let mut game = Game::default();
+        let ship = create_ship(2);
+        game.add_ship(Player::Beta, ship, 4).unwrap();
+
+        let second = wrapping_move(CELL, 1, Direction::Right);
+        let water = wrapping_move(CELL, 5, Direction::Down);
+        assert_eq!(game.shoot(Player::Alpha, water), Ok(Shot::Miss));
+        assert_eq!(game.shoot(Player::Alpha, CELL), Ok(Shot::Hit));
+        assert!(!game.is_sunk(Player::Beta, 4));
+        assert_eq!(game.shoot(Player::Alpha, second), Ok(Shot::Sunk(4)));
+        assert!(game.is_sunk(Player::Beta, 4));
+        assert!(!game.is_sunk(Player::Beta, 0));
+        assert_eq!(
+            game.shoots_alpha & create_surround_mask(ship),
+            create_surround_mask(ship)
+        );
+    }
+
+    #[test]
+    fn relaxed_and_salvo_rules() {
+        let mut game = Game {
+            rules: Rules::RELAXED,
+            ..Game::default()
+        };
+        let ship = create_ship(2);
+        game.add_ship(Player::Alpha, ship, 4).unwrap();
+        let below = wrapping_move(ship, 1, Direction::Down);
+        assert_eq!(game.add_ship(Player::Alpha, below, 3), Ok(()));
+
+        game.shoot(Player::Beta, ship).unwrap();
+        assert_eq!(game.shoots_beta, ship);
+        assert_eq!(game.shots_per_turn(Player::Alpha), 1);
+
+        game.rules = Rules::SALVO;
+        game.place_random_fleet(Player::Beta, &mut Rng::new(3));
+        assert_eq!(game.shots_per_turn(Player::Beta), SHIPS_COUNT);
+        assert_eq!(game.shots_per_turn(Player::Alpha), 1);
+    }
+
+    #[test]
+    fn concede_ends_the_game() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(2));
+        assert!(game.has_fleet(Player::Beta));
+        assert!(!game.is_over());
+        game.concede(Player::Beta);
+        game.concede(Player::Alpha);
+        assert_eq!(game.get_winner(), Some(Player::Alpha));
+    }
+
+    #[test]
+    fn replay_fires_the_moves_again() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(2));
+        let cells = [CELL, CELL >> 1, CELL >> 10];
+        let shots = cells.map(|cell| game.shoot(Player::Alpha, cell).unwrap());
+        game.concede(Player::Beta);
+
+        let start = game.without_shots();
+        assert_eq!((start.shoots_alpha, start.conceded), (0, None));
+        assert!(start.moves.is_empty());
+
+        let replayed: Vec<_> = game.replay().collect();
+        assert_eq!(replayed.len(), cells.len());
+        for (i, (mv, shot, after)) in replayed.into_iter().enumerate() {
+            assert_eq!(mv.cell.to_board(), cells[i]);
+            assert_eq!(shot, shots[i]);
+            assert_eq!(after.moves.len(), i + 1);
+        }
+    }
+
+    #[test]
+    fn moves_are_checked() {
+        let mv = |player, cell| Move {
+            player,
+            cell: Coord::from_board(cell).unwrap(),
+        };
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        assert_eq!(
+            game.play_move(mv(Player::Alpha, CELL)),
+            Err(MoveError::NoFleet)
+        );
+
+        game.place_random_fleet(Player::Beta, &mut Rng::new(2));
+        assert!(game.play_move(mv(Player::Alpha, CELL)).is_ok());
+        assert!(game.play_move(mv(Player::Beta, CELL)).is_ok());
+        assert_eq!(
+            game.play_move(mv(Player::Alpha, CELL)),
+            Err(MoveError::AlreadyShot)
+        );
+        assert_eq!(
+            game.shoot(Player::Alpha, CELL | CELL >> 1),
+            Err(MoveError::AlreadyShot)
+        );
+        assert_eq!(
+            game.play_move(mv(Player::Beta, CELL >> 1)),
+            Err(MoveError::OutOfTurn)
+        );
+
+        game.concede(Player::Beta);
+        assert_eq!(
+            game.play_move(mv(Player::Alpha, CELL >> 1)),
+            Err(MoveError::GameOver)
+        );
+        assert_eq!(game.moves.len(), 2);
+    }
+
+    #[test]
+    fn salvo_turns_follow_the_fleet() {
+        let mut game = Game {
+            rules: Rules::SALVO,
+            ..Game::default()
+        };
+        game.place_fleet(Player::Alpha, fleet_in_rows()).unwrap();
+        game.place_fleet(Player::Beta, fleet_in_rows()).unwrap();
+        assert_eq!(game.turn, None);
+
+        // Beta starts and sinks the patrol boat with the last two shots
+        let cells = ["J1", "J2", "J3", "A9", "B9"];
+        for cell in cells {
+            let cell = cell.parse().unwrap();
+            game.play_move(Move {
+                player: Player::Beta,
+                cell,
+            })
+            .unwrap();
+        }
+        assert_eq!(game.turn, Some((Player::Alpha, SHIPS_COUNT - 1)));
+
+        game.shoot(Player::Alpha, CELL).unwrap();
+        assert_eq!(game.turn, Some((Player::Alpha, SHIPS_COUNT - 2)));
+    }
+
+    #[test]
+    fn player_names_and_indices() {
+        for (i, player) in PLAYERS.into_iter().enumerate() {
+            assert_eq!(player.index(), i);
+            assert_eq!(Player::from_index(i), Some(player));
+            assert_eq!(player.name().parse(), Ok(player));
+        }
+        assert_eq!(Player::from_index(2), None);
+        assert_eq!("gamma".parse::<Player>(), Err(ParsePlayerError));
+        assert_eq!("Alpha".parse::<Player>(), Err(ParsePlayerError));
+    }
+
+    #[test]
+    fn fleets_are_checked() {
+        let mut random = Game::default();
+        random.place_random_fleet(Player::Beta, &mut Rng::new(8));
+        let fleet = random.get_ships(Player::Beta);
+
+        let mut game = Game::default();
+        assert_eq!(game.place_fleet(Player::Alpha, fleet), Ok(()));
+        assert_eq!(game.get_ships(Player::Alpha), fleet);
+
+        let mut bent = fleet;
+        bent[2] = create_ship(2) | wrapping_move(CELL, 1, Direction::Down);
+        assert!(game.place_fleet(Player::Beta, bent).is_err());
+
+        let mut swapped = fleet;
+        swapped.swap(0, 4);
+        assert!(game.place_fleet(Player::Beta, swapped).is_err());
+
+        // A long ship at the end of a row must not wrap into the next one
+        let mut wrapped = fleet;
+        wrapped[0] = wrapping_move(create_ship(5), 7, Direction::Right);
+        assert!(game.place_fleet(Player::Beta, wrapped).is_err());
+        assert_eq!(game.get_ships(Player::Beta), [0; SHIPS_COUNT]);
+
+        let mut partial = fleet;
+        partial[1] = 0;
+        assert!(game.place_fleet(Player::Beta, partial).is_err());
+        assert_eq!(game.place_partial_fleet(Player::Beta, partial), Ok(()));
+        assert!(!game.has_fleet(Player::Beta));
+        partial.swap(0, 4);
+        assert!(game.place_partial_fleet(Player::Beta, partial).is_err());
+    }
 }
@@ -0,0 +1,420 @@
+//! Just enough JSON for [`export`](crate::export): parsing into a
+//! [`Value`] and writing one back, indented. Objects keep their keys in
+//! order and may not repeat a key.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<const N: usize>(fields: [(&str, Value); N]) -> Value {
+        Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(text: impl Into<String>) -> Value {
+        Value::String(text.into())
+    }
+
+    /// Name of the kind of value, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        let (open, close, items) = match self {
+            Value::Null => return write!(f, "null"),
+            Value::Bool(value) => return write!(f, "{}", value),
+            Value::Number(number) => return write!(f, "{}", number),
+            Value::String(text) => return write_string(f, text),
+            Value::Array(items) if items.is_empty() => return write!(f, "[]"),
+            Value::Object(fields) if fields.is_empty() => return write!(f, "{{}}"),
+            Value::Array(items) => ('[', ']', items.len()),
+            Value::Object(fields) => ('{', '}', fields.len()),
+        };
+
+        // Arrays of plain values fit on one line
+        if let Value::Array(values) = self {
+            if values
+                .iter()
+                .all(|value| !matches!(value, Value::Array(_) | Value::Object(_)))
+            {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.write(f, indent)?;
+                }
+                return write!(f, "]");
+            }
+        }
+
+        writeln!(f, "{}", open)?;
+        for i in 0..items {
+            write!(f, "{:1$}", "", indent + 2)?;
+            match self {
+                Value::Array(values) => values[i].write(f, indent + 2)?,
+                Value::Object(fields) => {
+                    write_string(f, &fields[i].0)?;
+                    write!(f, ": ")?;
+                    fields[i].1.write(f, indent + 2)?;
+                }
+                _ => unreachable!(),
+            }
+            writeln!(f, "{}", if i + 1 < items { "," } else { "" })?;
+        }
+        write!(f, "{:1$}{2}", "", indent, close)
+    }
+}
+
+/// Indented JSON.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Where the text stopped being JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Parses one JSON value, with nothing but whitespace after it.
+pub fn parse(text: &str) -> Result<Value, JsonError> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.value(0)?;
+    parser.whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(_) => Err(parser.error("unexpected text after the value")),
+    }
+}
+
+/// Deeper documents are refused rather than overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error(format!("expected `{}`", expected))),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> JsonError {
+        let before = &self.text[..self.position];
+        JsonError {
+            line: before.matches('\n').count() + 1,
+            column: before.rsplit('\n').next().unwrap_or("").chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.whitespace();
+
+        match self.peek() {
+            Some('{') => self.object(depth),
+            Some('[') => self.array(depth),
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some(_) => {
+                for (word, value) in [
+                    ("null", Value::Null),
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                ] {
+                    if self.text[self.position..].starts_with(word) {
+                        self.position += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("unexpected end of text")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, JsonError> {
+        self.expect('{')?;
+        let mut fields: Vec<(String, Value)> = Vec::new();
+
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a key"));
+            }
+            let key_position = self.position;
+            let key = self.string()?;
+            if fields.iter().any(|(other, _)| *other == key) {
+                self.position = key_position;
+                return Err(self.error(format!("key `{}` appears twice", key)));
+            }
+            self.expect(':')?;
+            fields.push((key, self.value(depth + 1)?));
+
+            self.whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Object(fields)),
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, JsonError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut text = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("unknown escape")),
+                    };
+                    text.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in a string"))
+                }
+                Some(c) => text.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.text[self.position..].starts_with("\\u") {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                self.position += 2;
+                match self.hex4()? {
+                    low @ 0xdc00..=0xdfff => 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                    _ => return Err(self.error("unpaired surrogate")),
+                }
+            }
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("expected 4 hex digits"))?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("checked hex digits"))
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.position;
+        let digits = |parser: &mut Parser| {
+            let from = parser.position;
+            while let Some('0'..='9') = parser.peek() {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+
+        if self.peek() == Some('-') {
+            self.position += 1;
+        }
+        let leading_zero = self.peek() == Some('0');
+        let integer_start = self.position;
+        if !digits(self) || (leading_zero && self.position - integer_start > 1) {
+            return Err(self.error("malformed number"));
+        }
+        if self.peek() == Some('.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err(self.error("malformed number"));
+            }
+        }
+        if let Some('e' | 'E') = self.peek() {
+            self.position += 1;
+            if let Some('+' | '-') = self.peek() {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("malformed number"));
+            }
+        }
+
+        let number: f64 = self.text[start..self.position]
+            .parse()
+            .map_err(|_| self.error("malformed number"))?;
+        Ok(Value::Number(number))
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn values_round_trip() {
+        let text = r#"{"name": "sub\"\n", "cells": ["A1", "B2"], "moves": [{"n": -1.5e2}],
+            "empty": [], "none": null, "yes": true, "no": {}}"#;
+        let value = parse(text).unwrap();
+        let Value::Object(fields) = &value else {
+            panic!("expected an object");
+        };
+        assert_eq!(fields[0], (String::from("name"), Value::string("sub\"\n")));
+        assert_eq!(
+            fields[2].1,
+            Value::Array(vec![Value::object([("n", Value::Number(-150.0))])])
+        );
+
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+        assert_eq!(
+            parse(r#""\u00e9\ud83d\udea2""#).unwrap(),
+            Value::string("é🚢")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_json() {
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\": 1,}",
+            "{\"a\": 1, \"a\": 2}",
+            "{a: 1}",
+            "01",
+            "1.",
+            "-",
+            "\"tab\there\"",
+            "\"\\ud800\"",
+            "\"\\x\"",
+            "nul",
+            "[] []",
+            &"[".repeat(100),
+        ] {
+            assert!(parse(text).is_err(), "{}", text);
+        }
+
+        let err = parse("{\n  \"a\": 1,\n  \"a\": 2\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 3));
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+
+use crate::board_api::Direction;
+use crate::config::{parse_entries, read_config, ConfigError};
+use crate::term::Key;
+
+pub const KEYS_FILE: &str = "keys.conf";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Move(Direction),
+    /// Moves all the way to the edge of the board.
+    Jump(Direction),
+    Rotate,
+    Confirm,
+    Next,
+    Randomize,
+    Undo,
+    Save,
+    Quit,
+    Help,
+}
+
+pub const ACTIONS: [Action; 16] = [
+    Action::Move(Direction::Up),
+    Action::Move(Direction::Down),
+    Action::Move(Direction::Left),
+    Action::Move(Direction::Right),
+    Action::Jump(Direction::Left),
+    Action::Jump(Direction::Right),
+    Action::Jump(Direction::Up),
+    Action::Jump(Direction::Down),
+    Action::Rotate,
+    Action::Confirm,
+    Action::Next,
+    Action::Randomize,
+    Action::Undo,
+    Action::Save,
+    Action::Quit,
+    Action::Help,
+];
+
+impl Action {
+    /// Name of the action in the key binding file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Move(Direction::Up) => "up",
+            Action::Move(Direction::Down) => "down",
+            Action::Move(Direction::Left) => "left",
+            Action::Move(Direction::Right) => "right",
+            Action::Jump(Direction::Left) => "home",
+            Action::Jump(Direction::Right) => "end",
+            Action::Jump(Direction::Up) => "top",
+            Action::Jump(Direction::Down) => "bottom",
+            Action::Rotate => "rotate",
+            Action::Confirm => "confirm",
+            Action::Next => "next",
+            Action::Randomize => "randomize",
+            Action::Undo => "undo",
+            Action::Save => "save",
+            Action::Quit => "quit",
+            Action::Help => "help",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.into_iter().find(|action| action.name() == name)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Maps pressed keys to actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        use Key::Char;
+
+        let defaults: [(&[Key], Action); 14] = [
+            (
+                &[Char('k'), Char('w'), Key::Up],
+                Action::Move(Direction::Up),
+            ),
+            (
+                &[Char('j'), Char('s'), Key::Down],
+                Action::Move(Direction::Down),
+            ),
+            (
+                &[Char('h'), Char('a'), Key::Left],
+                Action::Move(Direction::Left),
+            ),
+            (
+                &[Char('l'), Char('d'), Key::Right],
+                Action::Move(Direction::Right),
+            ),
+            (&[Key::Home], Action::Jump(Direction::Left)),
+            (&[Key::End], Action::Jump(Direction::Right)),
+            (&[Char('f')], Action::Rotate),
+            (&[Key::Enter], Action::Confirm),
+            (&[Key::Tab], Action::Next),
+            (&[Char('r')], Action::Randomize),
+            (&[Char('u')], Action::Undo),
+            (&[Char('S')], Action::Save),
+            (&[Char('q'), Key::Esc], Action::Quit),
+            (&[Char('?')], Action::Help),
+        ];
+
+        let bindings = defaults
+            .into_iter()
+            .flat_map(|(keys, action)| keys.iter().map(move |&key| (key, action)))
+            .collect();
+
+        KeyMap { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Loads the key binding file from the configuration directory. Actions
+    /// that are not mentioned in the file keep their default keys.
+    pub fn load() -> Result<KeyMap, ConfigError> {
+        match read_config(KEYS_FILE)? {
+            Some(text) => KeyMap::parse(&text),
+            None => Ok(KeyMap::default()),
+        }
+    }
+
+    /// Parses lines like `rotate = f space`, where the value is a list of
+    /// keys separated by whitespace. Every line is applied before keys bound
+    /// twice are looked for, so the order of the lines does not matter.
+    pub fn parse(text: &str) -> Result<KeyMap, ConfigError> {
+        let mut map = KeyMap::default();
+        // Bindings from the file with their lines, for the errors
+        let mut bound_in_file: Vec<(Key, Action, usize)> = Vec::new();
+
+        for entry in parse_entries(text)? {
+            let Some(action) = Action::from_name(entry.name) else {
+                return Err(ConfigError::syntax(
+                    entry.line,
+                    format!("unknown action `{}`", entry.name),
+                ));
+            };
+
+            let mut keys = Vec::new();
+            for name in entry.value.split_whitespace() {
+                match parse_key(name) {
+                    Some(key) => keys.push(key),
+                    None => {
+                        return Err(ConfigError::syntax(
+                            entry.line,
+                            format!("unknown key `{}`", name),
+                        ))
+                    }
+                }
+            }
+
+            map.bindings.retain(|&(_, bound)| bound != action);
+            bound_in_file.retain(|&(_, bound, _)| bound != action);
+            bound_in_file.extend(keys.into_iter().map(|key| (key, action, entry.line)));
+        }
+
+        let defaults = map.bindings.len();
+        map.bindings
+            .extend(bound_in_file.iter().map(|&(key, action, _)| (key, action)));
+
+        // Defaults never share a key, so the second binding is from the file
+        for (i, &(key, bound)) in map.bindings.iter().enumerate() {
+            if let Some(j) = (i + 1..map.bindings.len()).find(|&j| map.bindings[j].0 == key) {
+                let (_, action, line) = bound_in_file[j - defaults];
+                return Err(ConfigError::syntax(
+                    line,
+                    format!(
+                        "key `{}` is bound to both {} and {}",
+                        key_name(key),
+                        bound,
+                        action
+                    ),
+                ));
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(bound, _)| bound == key)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = Key> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |&&(_, bound)| bound == action)
+            .map(|&(key, _)| key)
+    }
+
+    /// Human readable list of keys bound to the action, e.g. `k/w`.
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys(action).map(key_name).collect();
+
+        if names.is_empty() {
+            return String::from("unbound");
+        }
+        names.join("/")
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let key = match name {
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "enter" => Key::Enter,
+        "tab" => Key::Tab,
+        "backspace" => Key::Backspace,
+        "esc" => Key::Esc,
+        "space" => Key::Char(' '),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(key), None) if !key.is_control() => Key::Char(key),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(key)
+}
+
+pub fn key_name(key: Key) -> String {
+    let name = match key {
+        Key::Char(' ') => "Space",
+        Key::Char(key) => return key.to_string(),
+        Key::Enter => "Enter",
+        Key::Tab => "Tab",
+        Key::Backspace => "Backspace",
+        Key::Esc => "Esc",
+        Key::Up => "Up",
+        Key::Down => "Down",
+        Key::Left => "Left",
+        Key::Right => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::CtrlC => "Ctrl-C",
+        Key::Resize => "Resize",
+        Key::Click { .. } => "Click",
+    };
+
+    String::from(name)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn defaults_match_classic_controls() {
+        let keys = KeyMap::default();
+        assert_eq!(
+            keys.action(Key::Char('k')),
+            Some(Action::Move(Direction::Up))
+        );
+        assert_eq!(keys.action(Key::Up), Some(Action::Move(Direction::Up)));
+        assert_eq!(
+            keys.action(Key::Char('d')),
+            Some(Action::Move(Direction::Right))
+        );
+        assert_eq!(keys.action(Key::Char('f')), Some(Action::Rotate));
+        assert_eq!(keys.action(Key::Enter), Some(Action::Confirm));
+        assert_eq!(keys.action(Key::Char('z')), None);
+    }
+
+    #[test]
+    fn rebinding_replaces_default_keys() {
+        let keys = KeyMap::parse("randomize = x\nrotate = space r").unwrap();
+        assert_eq!(keys.action(Key::Char(' ')), Some(Action::Rotate));
+        assert_eq!(keys.action(Key::Char('r')), Some(Action::Rotate));
+        assert_eq!(keys.action(Key::Char('f')), None);
+        assert_eq!(keys.action(Key::Char('x')), Some(Action::Randomize));
+        assert_eq!(keys.describe(Action::Rotate), "Space/r");
+
+        // Taking a key first and rebinding its old action later is fine
+        let swapped = KeyMap::parse("rotate = space r\nrandomize = x").unwrap();
+        for key in [Key::Char(' '), Key::Char('r'), Key::Char('x')] {
+            assert_eq!(swapped.action(key), keys.action(key));
+        }
+    }
+
+    #[test]
+    fn conflicting_bindings() {
+        match KeyMap::parse("\nrotate = k") {
+            Err(ConfigError::Syntax { line: 2, message }) => {
+                assert!(message.contains("up"), "{}", message)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conflicts_do_not_depend_on_line_order() {
+        for text in ["rotate = x\nrandomize = x", "randomize = x\nrotate = x"] {
+            match KeyMap::parse(text) {
+                Err(ConfigError::Syntax { line: 2, .. }) => {}
+                other => panic!("unexpected {:?} for {:?}", other, text),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert!(KeyMap::parse("jump = j").is_err());
+        assert!(KeyMap::parse("up = ctrl").is_err());
+        assert_eq!(
+            KeyMap::parse("home = 0").unwrap().action(Key::Char('0')),
+            Some(Action::Jump(Direction::Left))
+        );
+    }
+}
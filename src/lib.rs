@@ -0,0 +1,62 @@
+//! Battleship on a 10x10 board. Boards are `u128` bitboards, see
+//! [`board_api`], and [`Game`] holds both fleets and the shots at them.
+//!
+//! The engine has no dependencies and does not allocate. Without the `std`
+//! feature it builds with `#![no_std]`, see the `no_std_check` crate. The
+//! terminal interface and network play need the `tui` feature. Both are on
+//! by default:
+//!
+//! ```toml
+//! battleship = { version = "0.1", default-features = false }
+//! ```
+
+// Tests always have std, they print and collect
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+pub mod ai;
+pub mod analysis;
+pub mod board_api;
+pub mod codec;
+pub mod constants;
+pub mod coord;
+pub mod game;
+pub mod log;
+pub mod rng;
+pub mod rules;
+pub mod session;
+pub mod text;
+pub mod view;
+
+#[cfg(feature = "std")]
+pub mod accessible;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod rating;
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "std")]
+pub mod save;
+#[cfg(feature = "std")]
+pub mod stats;
+
+#[cfg(feature = "tui")]
+pub mod front;
+#[cfg(feature = "tui")]
+pub mod keys;
+#[cfg(feature = "tui")]
+pub mod net;
+#[cfg(feature = "tui")]
+pub mod render;
+#[cfg(feature = "tui")]
+pub mod term;
+#[cfg(feature = "tui")]
+pub mod theme;
+
+pub use game::{Game, Interrupt, Player, Shot};
+pub use rules::Rules;
+pub use view::PlayerView;
@@ -0,0 +1,118 @@
+//! The shots of a game in the order they were fired.
+
+use crate::constants::BOARD_COUNT;
+use crate::coord::Coord;
+use crate::game::Player;
+
+/// Every cell shot by both players.
+pub const MOVES_CAPACITY: usize = 2 * BOARD_COUNT;
+
+/// One cell fired at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub player: Player,
+    pub cell: Coord,
+}
+
+impl Move {
+    /// The cell index, with the top bit set for Beta.
+    pub const fn to_byte(self) -> u8 {
+        self.cell.index() as u8 | (self.player.index() as u8) << 7
+    }
+
+    pub const fn from_byte(byte: u8) -> Option<Move> {
+        let player = match byte & 0x80 {
+            0 => Player::Alpha,
+            _ => Player::Beta,
+        };
+        match Coord::from_index((byte & 0x7f) as usize) {
+            Some(cell) => Some(Move { player, cell }),
+            None => None,
+        }
+    }
+}
+
+/// Moves stored as bytes, so a `Game` stays small and `Copy`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoveLog {
+    moves: [u8; MOVES_CAPACITY],
+    len: usize,
+}
+
+impl Default for MoveLog {
+    fn default() -> Self {
+        MoveLog {
+            moves: [0; MOVES_CAPACITY],
+            len: 0,
+        }
+    }
+}
+
+impl MoveLog {
+    /// Adds a move. A full log only happens when cells are shot twice, and
+    /// then the move is dropped.
+    pub fn push(&mut self, mv: Move) {
+        debug_assert!(self.len < MOVES_CAPACITY, "move log is full");
+        if self.len < MOVES_CAPACITY {
+            self.moves[self.len] = mv.to_byte();
+            self.len += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<Move> {
+        self.as_bytes()
+            .get(index)
+            .copied()
+            .and_then(Move::from_byte)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Move> + '_ {
+        self.as_bytes()
+            .iter()
+            .filter_map(|&byte| Move::from_byte(byte))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.moves[..self.len]
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn moves_round_trip() {
+        let mut log = MoveLog::default();
+        assert!(log.is_empty());
+
+        let moves = [
+            Move {
+                player: Player::Alpha,
+                cell: Coord::new(2, 4),
+            },
+            Move {
+                player: Player::Beta,
+                cell: Coord::new(9, 9),
+            },
+        ];
+        for mv in moves {
+            log.push(mv);
+        }
+
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.get(1), Some(moves[1]));
+        assert_eq!(log.get(2), None);
+        assert!(log.iter().eq(moves));
+        assert_eq!(log.as_bytes(), [42, 0x80 | 99]);
+        assert_eq!(Move::from_byte(100), None);
+    }
+}
@@ -2,58 +2,980 @@
 // #![allow(unused_variables)]
 // #![allow(unused_imports)]
 
-use game::{Game, Player, SHIP_SIZES};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use front::{clear, IO, wait_for_enter};
+use battleship::ai::{self, Strategy, STRATEGY_NAMES};
+use battleship::analysis::Review;
+use battleship::front::IO;
+use battleship::game::{Game, Interrupt, Player, Shot, PLAYERS, SHIPS_COUNT};
+use battleship::keys::{self, KeyMap};
+use battleship::log::{Move, MOVES_CAPACITY};
+use battleship::net::{self, Connection, Message};
+use battleship::rating::{self, Ratings};
+use battleship::record::{self, Record};
+use battleship::rng::Rng;
+use battleship::rules::Rules;
+use battleship::session::{self, Frontend};
+use battleship::stats::{self, Stats};
+use battleship::text::GameText;
+use battleship::theme::{self, Theme};
+use battleship::view::PlayerView;
+use battleship::{accessible, codec, export, save, term};
+use cli::{Command, DisplayOptions, PlayOptions, SimulateOptions, TournamentOptions};
 
-mod board_api;
-mod constants;
-mod front;
-mod game;
+mod cli;
+
+/// Who makes the moves of one player.
+enum Controller {
+    /// Somebody at this terminal.
+    Human,
+    Computer(Box<dyn Strategy>),
+    /// A player who joined over the network.
+    Remote(Connection),
+}
 
 fn main() {
-    let mut game = Game::default();
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("battleship: {}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Play(options) if options.display.accessible => play_accessible(&options),
+        Command::Play(options) => play_local(&options),
+        Command::Replay { file, display } => replay(&file, &display),
+        Command::Simulate(options) => simulate(&options),
+        Command::Host { address, options } => host(&address, &options),
+        Command::Join {
+            address,
+            display,
+            name,
+        } => join(&address, &display, name.as_deref()),
+        Command::Analyze { file } => analyze(&file),
+        Command::Export { file, text } => export(file.as_deref(), text),
+        Command::Import { file } => import(&file),
+        Command::Stats { name } => print_stats(name.as_deref()),
+        Command::Tournament(options) => tournament(&options),
+        Command::Ratings => match Ratings::load() {
+            Ok(ratings) => print_ratings(&ratings),
+            Err(err) => fail(&format!("{}: {}", rating::RATINGS_FILE, err)),
+        },
+        Command::Help => print!("{}", cli::USAGE),
+        Command::Version => println!("battleship {}", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+fn fail(text: &str) -> ! {
+    eprintln!("battleship: {}", text);
+    std::process::exit(2);
+}
+
+/// Loads the settings and puts the terminal in raw mode. The terminal is
+/// restored when the returned guard is dropped.
+fn set_up_terminal(display: &DisplayOptions) -> (IO, term::RawMode) {
+    let keys = match KeyMap::load() {
+        Ok(keys) => keys,
+        Err(err) => fail(&format!("{}: {}", keys::KEYS_FILE, err)),
+    };
+
+    let theme = match Theme::load(display.theme.and_then(Theme::by_name), display.palette) {
+        Ok(theme) => theme,
+        Err(err) => fail(&format!("{}: {}", theme::THEME_FILE, err)),
+    };
+
+    let raw_mode = match term::RawMode::enable() {
+        Ok(raw_mode) => raw_mode,
+        Err(err) => fail(&format!("cannot set up the terminal: {}", err)),
+    };
+
     let mut io = IO::default();
+    io.keys = keys;
+    io.theme = theme;
+    (io, raw_mode)
+}
+
+fn play_local(options: &PlayOptions) {
+    let new_game = new_game(options);
+    let seed = game_seed(options);
+    let (mut io, _raw_mode) = set_up_terminal(&options.display);
+    io.rng = Rng::new(seed);
+
+    // A resumed game was not started from this seed
+    let (mut game, turn, seed) = match save::take_saved_game() {
+        Ok(Some((game, turn)))
+            if io.ask("A saved game was found. Resume it? A new game discards it.") =>
+        {
+            (game, turn, None)
+        }
+        Ok(_) => (new_game, Player::Alpha, Some(seed)),
+        Err(err) => {
+            io.clear();
+            io.wait_for_enter(&format!("Cannot load the saved game: {}", err));
+            (new_game, Player::Alpha, Some(seed))
+        }
+    };
+
+    let beta = match options.ai.and_then(ai::strategy_by_name) {
+        Some(strategy) => Controller::Computer(strategy),
+        None => Controller::Human,
+    };
+    let mut controllers = [Controller::Human, beta];
+
+    if play(&mut game, &mut io, turn, &mut controllers).is_ok() {
+        let names = player_names(options);
+        let mut notes = update_stats(&game, names.map(Some));
+        notes.extend(update_ratings(
+            names,
+            game.get_winner().expect("the game is over"),
+        ));
+        display_last_scene(&mut io, &game, &notes);
+        if options.analysis {
+            io.display_report("Analysis", &Review::new(&game).summary());
+        }
+        if let Err(err) = record_game(&game, names, seed) {
+            io.clear();
+            io.wait_for_enter(&format!("Cannot record the game: {}", err));
+        }
+    }
+}
+
+/// Name of the second player sharing the terminal, unless they gave one.
+const GUEST: &str = "guest";
+
+/// Names of Alpha and Beta in a local game.
+fn player_names(options: &PlayOptions) -> [&str; 2] {
+    let beta = match options.ai {
+        Some(strategy) => strategy,
+        None => options.opponent.as_deref().unwrap_or(GUEST),
+    };
+    [player_name(options.name.as_deref()), beta]
+}
+
+/// The name given with `--name`, or else the login name if it can be
+/// one.
+fn player_name(name: Option<&str>) -> &str {
+    if let Some(name) = name {
+        return name;
+    }
+    static USER: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    USER.get_or_init(|| match std::env::var("USER") {
+        Ok(user) if stats::is_valid_name(&user) => user,
+        _ => String::from("player"),
+    })
+}
+
+/// Adds a finished game to the statistics of the named players and
+/// returns a summary of each for the end of the game.
+fn update_stats(game: &Game, names: [Option<&str>; 2]) -> Vec<String> {
+    let mut stats = match Stats::load() {
+        Ok(stats) => stats,
+        Err(err) => return vec![format!("Cannot load the statistics: {}", err)],
+    };
+    stats.add_game(game, names);
+    if let Err(err) = stats.save() {
+        return vec![format!("Cannot save the statistics: {}", err)];
+    }
+
+    names
+        .into_iter()
+        .flatten()
+        .map(|name| stats::summary(name, stats.profile(name).expect("added above")))
+        .collect()
+}
+
+/// Rates a finished game and returns the new rating of each player, for
+/// the end of the game.
+fn update_ratings(names: [&str; 2], winner: Player) -> Vec<String> {
+    let mut ratings = match Ratings::load() {
+        Ok(ratings) => ratings,
+        Err(err) => return vec![format!("Cannot load the ratings: {}", err)],
+    };
+    let changes = ratings.add_game(names, winner);
+    if let Err(err) = ratings.save() {
+        return vec![format!("Cannot save the ratings: {}", err)];
+    }
+
+    names
+        .into_iter()
+        .zip(changes)
+        .map(|(name, change)| {
+            let rating = ratings.rating(name).rating;
+            format!("{} is now rated {:.0} ({:+.0}).", name, rating, change)
+        })
+        .collect()
+}
+
+/// The seed asked for, or a random one, so that every new game can be
+/// recorded with its seed.
+fn game_seed(options: &PlayOptions) -> u64 {
+    options.seed.unwrap_or_else(|| Rng::default().next_u64())
+}
+
+/// Adds a finished game to the records, see [`record`].
+fn record_game(game: &Game, names: [&str; 2], seed: Option<u64>) -> std::io::Result<PathBuf> {
+    let record = Record {
+        alpha: String::from(names[0]),
+        beta: String::from(names[1]),
+        date: record::today(),
+        seed,
+        game: *game,
+    };
+    record::save_record(&record)
+}
+
+/// A game by the options, with the local player's fleet placed if a
+/// layout was given.
+fn new_game(options: &PlayOptions) -> Game {
+    let mut game = Game {
+        rules: options.rules,
+        ..Game::default()
+    };
+
+    if let Some(file) = &options.fleet {
+        let fleet = std::fs::read_to_string(file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                export::fleet_from_json(&text, options.rules).map_err(|err| err.to_string())
+            });
+        match fleet {
+            Ok(fleet) => {
+                game.place_fleet(Player::Alpha, fleet)
+                    .expect("checked by fleet_from_json");
+            }
+            Err(err) => fail(&format!("{}: {}", file.display(), err)),
+        }
+    }
+
+    game
+}
+
+fn display_last_scene(io: &mut IO, game: &Game, notes: &[String]) {
+    let views = PLAYERS.map(|player| PlayerView::new(game, player));
+    io.display_last_scene([&views[0], &views[1]], game.conceded, notes);
+}
+
+/// Plays on stdin and stdout without raw mode, so the terminal and any
+/// screen reader keep working as usual.
+fn play_accessible(options: &PlayOptions) {
+    let new_game = new_game(options);
+    let seed = game_seed(options);
+    let stdin = std::io::stdin();
+    let mut io = accessible::LineIO::new(stdin.lock(), std::io::stdout());
+    io.rng = Rng::new(seed);
+
+    let (mut game, turn, seed) = match save::take_saved_game() {
+        Ok(Some((game, turn))) => {
+            match io.ask("A saved game was found. Resume it? A new game discards it.") {
+                Ok(true) => (game, turn, None),
+                Ok(false) => (new_game, Player::Alpha, Some(seed)),
+                Err(_) => return,
+            }
+        }
+        Ok(None) => (new_game, Player::Alpha, Some(seed)),
+        Err(err) => {
+            io.say(&format!("Cannot load the saved game: {}.", err));
+            (new_game, Player::Alpha, Some(seed))
+        }
+    };
+
+    let mut computer = options.ai.and_then(ai::strategy_by_name);
+    let computer = computer
+        .as_mut()
+        .map(|strategy| strategy.as_mut() as &mut dyn Strategy);
+    if accessible::play(&mut game, &mut io, turn, computer).is_ok() {
+        io.say(&accessible::describe_result(&game));
+        let names = player_names(options);
+        let mut notes = update_stats(&game, names.map(Some));
+        notes.extend(update_ratings(
+            names,
+            game.get_winner().expect("the game is over"),
+        ));
+        if options.analysis {
+            notes.extend(Review::new(&game).summary());
+        }
+        for note in notes {
+            io.say(&note);
+        }
+        match record_game(&game, names, seed) {
+            Ok(path) => io.say(&format!("The game was recorded in {}.", path.display())),
+            Err(err) => io.say(&format!("Cannot record the game: {}.", err)),
+        }
+    }
+}
+
+/// Plays the game from the given state until somebody wins. Only quitting
+/// is returned as an error, a concession just ends the game. A lost
+/// connection is shown and then counts as quitting.
+fn play(
+    game: &mut Game,
+    io: &mut IO,
+    turn: Player,
+    controllers: &mut [Controller; 2],
+) -> Result<(), Interrupt> {
+    // Players sharing the terminal must not see each other's boards
+    let hot_seat = controllers
+        .iter()
+        .all(|controller| matches!(controller, Controller::Human));
+
+    let mut frontend = Terminal {
+        io,
+        controllers,
+        hot_seat,
+        first: turn,
+        incoming: [Vec::new(), Vec::new()],
+    };
+    session::play(game, turn, &mut frontend)
+}
+
+/// Players at this terminal, computers and a remote player.
+struct Terminal<'a> {
+    io: &'a mut IO,
+    controllers: &'a mut [Controller; 2],
+    hot_seat: bool,
+    /// Who starts, kept by saves made before the first shot
+    first: Player,
+    /// Shots each player has not seen yet, by player
+    incoming: [Vec<(u128, Shot)>; 2],
+}
+
+impl Frontend for Terminal<'_> {
+    fn place_fleet(&mut self, game: &mut Game, player: Player) -> Result<(), Interrupt> {
+        let placement_table = [
+            (Player::Alpha, "Player Alpha, place your ships!"),
+            (Player::Beta, "Player Beta, place your ships!"),
+        ];
+
+        match &mut self.controllers[player.index()] {
+            Controller::Human => {
+                if self.hot_seat {
+                    let (_, text) = placement_table[player.index()];
+                    self.io.clear();
+                    self.io.wait_for_enter(text);
+                }
+                let save = |ships: &_| save::save_placing(game, player, ships, self.first);
+                let ships = self
+                    .io
+                    .read_fleet(game.rules, game.get_ships(player), &save)?;
+                game.place_fleet(player, ships)
+                    .expect("placed by the rules");
+            }
+            Controller::Computer(_) => game.place_random_fleet(player, &mut self.io.rng),
+            Controller::Remote(connection) => {
+                let view = PlayerView::new(game, player.other());
+                self.io.display_waiting(
+                    &view,
+                    "Waiting for the other player to place their ships...",
+                );
+                match connection.receive() {
+                    Ok(Message::Fleet(ships)) if game.place_fleet(player, ships).is_ok() => {}
+                    Ok(Message::Concede) => return Err(Interrupt::Concede),
+                    Ok(_) => {
+                        return Err(connection_lost(self.io, "the other player broke the rules"))
+                    }
+                    Err(err) => return Err(connection_lost(self.io, &err.to_string())),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, _game: &Game) {
+        if self.hot_seat {
+            self.io.clear();
+            self.io.wait_for_enter("Game starts!");
+        }
+    }
+
+    fn start_turn(&mut self, _game: &Game, player: Player, _shots: usize) -> Result<(), Interrupt> {
+        let control_table = [
+            (Player::Alpha, "Now player Alpha shoots!"),
+            (Player::Beta, "Now player Beta shoots!"),
+        ];
 
+        if self.hot_seat {
+            let (_, text) = control_table[player.index()];
+            self.io.clear();
+            self.io.wait_for_enter(text);
+        }
+        Ok(())
+    }
 
-    clear();
-    wait_for_enter("Player Alpha, place your ships!");
-    for (i, size) in SHIP_SIZES.into_iter().enumerate() {
-        let new_ship = io.read_new_ship(&game, Player::Alpha, size);
-        game.add_ship(Player::Alpha, new_ship, i).unwrap();
+    fn next_shot(&mut self, game: &Game, player: Player) -> Result<u128, Interrupt> {
+        let view = PlayerView::new(game, player);
+        match &mut self.controllers[player.index()] {
+            Controller::Human => {
+                let unseen = std::mem::take(&mut self.incoming[player.index()]);
+                self.io
+                    .read_shoot(&view, &unseen, &|| save::save_game(game, player))
+            }
+            Controller::Computer(strategy) => Ok(strategy.next_shot(&view, &mut self.io.rng)),
+            Controller::Remote(connection) => {
+                let own = PlayerView::new(game, player.other());
+                self.io
+                    .display_waiting(&own, "Waiting for the other player to shoot...");
+                match receive_shot(connection, &view) {
+                    Ok(Some(shoot)) => Ok(shoot),
+                    Ok(None) => Err(Interrupt::Concede),
+                    Err(err) => Err(connection_lost(self.io, &err.to_string())),
+                }
+            }
+        }
     }
 
-    clear();
-    wait_for_enter("Player Beta, place your ships!");
-    for (i, size) in SHIP_SIZES.into_iter().enumerate() {
-        let new_ship = io.read_new_ship(&game, Player::Beta, size);
-        game.add_ship(Player::Beta, new_ship, i).unwrap();
+    fn fired(
+        &mut self,
+        game: &Game,
+        player: Player,
+        shoot: u128,
+        shot: Shot,
+    ) -> Result<(), Interrupt> {
+        self.incoming[player.other().index()].push((shoot, shot));
+
+        for (i, controller) in self.controllers.iter_mut().enumerate() {
+            if let Controller::Remote(connection) = controller {
+                let view = PlayerView::new(game, PLAYERS[i]);
+                let sent = connection.send(&Message::View(view)).and_then(|()| {
+                    connection.send(&Message::Shot {
+                        player,
+                        shoot,
+                        shot,
+                    })
+                });
+                if let Err(err) = sent {
+                    return Err(connection_lost(self.io, &err.to_string()));
+                }
+            }
+        }
+
+        if let Controller::Human = self.controllers[player.index()] {
+            self.io
+                .display_scene_after_shoot(&PlayerView::new(game, player), shoot, shot);
+        }
+        Ok(())
     }
+}
 
-    clear();
-    wait_for_enter("Game starts!");
+/// Asks the remote player for a shot. `None` means they conceded.
+fn receive_shot(connection: &mut Connection, view: &PlayerView) -> std::io::Result<Option<u128>> {
+    connection.send(&Message::View(*view))?;
+    connection.send(&Message::Turn)?;
 
-    let control_table = [
-        (Player::Alpha, "Now player Alpha shoots!"),
-        (Player::Beta, "Now player Beta shoots!"),
-    ];
+    match connection.receive()? {
+        Message::Shoot(shoot) if shoot & view.shots == 0 => Ok(Some(shoot)),
+        Message::Concede => Ok(None),
+        _ => Err(std::io::Error::other("the other player broke the rules")),
+    }
+}
+
+fn connection_lost(io: &mut IO, reason: &str) -> Interrupt {
+    io.clear();
+    io.wait_for_enter(&format!("Connection lost: {}", reason));
+    Interrupt::Quit
+}
 
-    let mut step = 0;
-    while !game.is_over() {
-        clear();
-        wait_for_enter(control_table[step % 2].1);
+/// Hosts a network game. The host plays Alpha and keeps the game.
+fn host(address: &str, options: &PlayOptions) {
+    let mut game = new_game(options);
+    let seed = game_seed(options);
+    let (mut io, _raw_mode) = set_up_terminal(&options.display);
+    io.saving_disabled = true;
+    io.rng = Rng::new(seed);
+
+    io.clear();
+    io.wait_for_enter(&format!(
+        "Hosting {} rules on {}. Tell the other player to join.",
+        options.rules.name, address
+    ));
+    io.clear();
+    io.display_waiting(
+        &PlayerView::new(&Game::default(), Player::Alpha),
+        "Waiting for a player to join...",
+    );
+
+    let name = player_name(options.name.as_deref());
+    let (connection, remote) = match net::host(address, options.rules, name) {
+        Ok(hosted) => hosted,
+        Err(err) => {
+            connection_lost(&mut io, &err.to_string());
+            return;
+        }
+    };
+
+    let mut controllers = [Controller::Human, Controller::Remote(connection)];
+    if play(&mut game, &mut io, Player::Alpha, &mut controllers).is_err() {
+        return;
+    }
+
+    if let Controller::Remote(connection) = &mut controllers[1] {
+        let over = Message::Over {
+            winner: game.get_winner().expect("the game is over"),
+            conceded: game.conceded.is_some(),
+            fleet: game.ships_alpha,
+        };
+        // The other player may have left after conceding
+        let _ = connection.send(&over);
+    }
+    let names = [name, remote.as_str()];
+    let mut notes = update_stats(&game, names.map(Some));
+    notes.extend(update_ratings(
+        names,
+        game.get_winner().expect("the game is over"),
+    ));
+    display_last_scene(&mut io, &game, &notes);
+    if options.analysis {
+        io.display_report("Analysis", &Review::new(&game).summary());
+    }
+    if let Err(err) = record_game(&game, names, Some(seed)) {
+        io.clear();
+        io.wait_for_enter(&format!("Cannot record the game: {}", err));
+    }
+}
 
-        let shoot = io.read_shoot(
-            &game,
-            control_table[step % 2].0,
+/// Joins a network game as Beta. Only the host knows the whole game, this
+/// side gets views of it, so only the ratings are updated here.
+fn join(address: &str, display: &DisplayOptions, name: Option<&str>) {
+    let (mut io, _raw_mode) = set_up_terminal(display);
+    io.saving_disabled = true;
+
+    io.clear();
+    io.display_waiting(
+        &PlayerView::new(&Game::default(), Player::Beta),
+        &format!("Joining {}...", address),
+    );
+    let name = player_name(name);
+    let (mut connection, rules, host) = match net::join(address, name) {
+        Ok(joined) => joined,
+        Err(err) => {
+            connection_lost(&mut io, &err.to_string());
+            return;
+        }
+    };
+
+    if let Err(err) = play_remote(&mut io, &mut connection, rules, [&host, name]) {
+        connection_lost(&mut io, &err.to_string());
+    }
+}
+
+fn play_remote(
+    io: &mut IO,
+    connection: &mut Connection,
+    rules: Rules,
+    names: [&str; 2],
+) -> std::io::Result<()> {
+    let player = Player::Beta;
+    // Only the host keeps the game, so there is nothing to save here
+    let unsaved = || Err(std::io::Error::other("network games cannot be saved"));
+
+    io.clear();
+    io.wait_for_enter(&format!("Joined a game with {} rules.", rules.name));
+    let ships = match io.read_fleet(rules, [0; SHIPS_COUNT], &|_| unsaved()) {
+        Ok(ships) => {
+            connection.send(&Message::Fleet(ships))?;
+            ships
+        }
+        Err(Interrupt::Concede) => {
+            connection.send(&Message::Concede)?;
+            [0; SHIPS_COUNT]
+        }
+        Err(Interrupt::Quit) => return Ok(()),
+    };
+
+    let mut view = PlayerView {
+        ships,
+        ..PlayerView::new(&Game::default(), player)
+    };
+    let mut incoming = Vec::new();
+    loop {
+        io.display_waiting(&view, "Waiting for the other player...");
+
+        match connection.receive()? {
+            Message::View(update) if update.player == player => view = update,
+            Message::Shot {
+                player: shooter,
+                shoot,
+                shot,
+            } if shooter == player => io.display_scene_after_shoot(&view, shoot, shot),
+            Message::Shot { shoot, shot, .. } => incoming.push((shoot, shot)),
+            Message::Turn => {
+                let unseen = std::mem::take(&mut incoming);
+                match io.read_shoot(&view, &unseen, &unsaved) {
+                    Ok(shoot) => connection.send(&Message::Shoot(shoot))?,
+                    Err(Interrupt::Concede) => connection.send(&Message::Concede)?,
+                    Err(Interrupt::Quit) => return Ok(()),
+                }
+            }
+            Message::Over {
+                winner,
+                conceded,
+                fleet,
+            } => {
+                view.winner = Some(winner);
+                let host = view.enemy_view(fleet);
+                let notes = update_ratings(names, winner);
+                io.display_last_scene([&host, &view], conceded.then(|| winner.other()), &notes);
+                return Ok(());
+            }
+            _ => return Err(std::io::Error::other("the host broke the rules")),
+        }
+    }
+}
+
+/// Lets computer players play against each other and prints the results.
+/// Who starts alternates between games.
+fn simulate(options: &SimulateOptions) {
+    let mut rng = options.seed.map_or_else(Rng::default, Rng::new);
+    let mut wins = [0; 2];
+    // Shots fired in the games each player won
+    let mut winning_shots = [0; 2];
+    let mut output = options.output.as_ref().map(|file| {
+        match std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+        {
+            Ok(output) => std::io::BufWriter::new(output),
+            Err(err) => fail(&format!("{}: {}", file.display(), err)),
+        }
+    });
+
+    for round in 0..options.games {
+        let first = PLAYERS[round % 2];
+        let (game, next, fired) =
+            play_computers(options.strategies, options.rules, first, &mut rng);
+
+        if let Some(output) = &mut output {
+            let record = codec::to_vec(&game, next).expect("a game fits in a record");
+            if let Err(err) = output.write_all(&record) {
+                fail(&format!("cannot write the games: {}", err));
+            }
+        }
+
+        let winner = game.get_winner().expect("the game is over").index();
+        wins[winner] += 1;
+        winning_shots[winner] += fired[winner];
+    }
+
+    if let Some(Err(err)) = output.as_mut().map(Write::flush) {
+        fail(&format!("cannot write the games: {}", err));
+    }
+
+    println!("{} games, {} rules", options.games, options.rules.name);
+    for (i, name) in ["Alpha", "Beta"].into_iter().enumerate() {
+        let average = match wins[i] {
+            0 => String::from("-"),
+            wins => format!("{:.1}", winning_shots[i] as f64 / wins as f64),
+        };
+        println!(
+            "{} ({}): {} wins, {} shots per win",
+            name, options.strategies[i], wins[i], average
         );
-        game.shoot(control_table[step % 2].0, shoot);
-        io.display_scene_after_shoot(
-            &game,
-            control_table[step % 2].0,
+    }
+}
+
+/// Plays one whole game between the strategies named, Alpha first in the
+/// names. Returns the finished game, who would shoot next and how many
+/// shots each player fired.
+fn play_computers(
+    names: [&str; 2],
+    rules: Rules,
+    first: Player,
+    rng: &mut Rng,
+) -> (Game, Player, [usize; 2]) {
+    let [mut alpha, mut beta] =
+        names.map(|name| ai::strategy_by_name(name).expect("checked by the parser"));
+    ai::play_computers(
+        rules,
+        [alpha.as_mut(), beta.as_mut()],
+        first,
+        MOVES_CAPACITY,
+        rng,
+    )
+}
+
+/// Lets every computer player play every other one and rates the games.
+/// Each pair plays the given number of games, taking turns to start.
+fn tournament(options: &TournamentOptions) {
+    let mut rng = options.seed.map_or_else(Rng::default, Rng::new);
+    let mut ratings = match Ratings::load() {
+        Ok(ratings) => ratings,
+        Err(err) => fail(&format!("{}: {}", rating::RATINGS_FILE, err)),
+    };
+
+    println!(
+        "{} games a pair, {} rules",
+        options.games, options.rules.name
+    );
+    for (i, alpha) in STRATEGY_NAMES.into_iter().enumerate() {
+        for beta in STRATEGY_NAMES.into_iter().skip(i + 1) {
+            let mut wins = [0; 2];
+            for round in 0..options.games {
+                let first = PLAYERS[round % 2];
+                let (game, _, _) = play_computers([alpha, beta], options.rules, first, &mut rng);
+                let winner = game.get_winner().expect("the game is over");
+                ratings.add_game([alpha, beta], winner);
+                wins[winner.index()] += 1;
+            }
+            println!("{} {} - {} {}", alpha, wins[0], wins[1], beta);
+        }
+    }
+
+    if let Err(err) = ratings.save() {
+        fail(&format!("cannot save the ratings: {}", err));
+    }
+    println!();
+    print_ratings(&ratings);
+}
+
+/// Prints the leaderboard.
+fn print_ratings(ratings: &Ratings) {
+    let leaderboard = ratings.leaderboard();
+    if leaderboard.is_empty() {
+        println!("Nobody has finished a game yet.");
+        return;
+    }
+
+    let width = leaderboard
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("player".len());
+    println!("rank  {:width$}  rating  games", "player");
+    for (rank, (name, rating)) in leaderboard.into_iter().enumerate() {
+        println!(
+            "{:>4}  {:width$}  {:>6.0}  {:>5}",
+            rank + 1,
+            name,
+            rating.rating,
+            rating.games
         );
-        step += 1;
     }
+}
+
+/// A game file in any of the formats.
+enum GameFile {
+    /// A saved game, or a game in JSON, see [`export`].
+    Game(Game, Player),
+    Record(Record),
+}
 
-    io.display_last_scene(&game);
+fn read_any_game_file(file: &Path) -> GameFile {
+    let bytes = match std::fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(err) => fail(&format!("{}: {}", file.display(), err)),
+    };
+    let text = || std::str::from_utf8(&bytes).map_err(|err| err.to_string());
+
+    let game = match bytes.trim_ascii_start().first() {
+        Some(b'{') => text().and_then(|text| {
+            let (game, turn) = export::game_from_json(text).map_err(|err| err.to_string())?;
+            Ok(GameFile::Game(game, turn))
+        }),
+        Some(b'[') => text().and_then(|text| {
+            let record = record::parse(text).map_err(|err| err.to_string())?;
+            Ok(GameFile::Record(record))
+        }),
+        _ => match save::decode(&bytes) {
+            Ok((game, turn)) => Ok(GameFile::Game(game, turn)),
+            Err(err) => Err(err.to_string()),
+        },
+    };
+    game.unwrap_or_else(|err| fail(&format!("{}: {}", file.display(), err)))
+}
+
+/// Reads a game from any of the file formats. Records do not say whose
+/// turn it is, so it follows from the moves, and Alpha starts a game
+/// without any.
+fn read_game_file(file: &Path) -> (Game, Player) {
+    match read_any_game_file(file) {
+        GameFile::Game(game, turn) => (game, turn),
+        GameFile::Record(record) => {
+            let turn = record.game.turn.map_or(Player::Alpha, |(player, _)| player);
+            (record.game, turn)
+        }
+    }
+}
+
+/// Plays a record back shot by shot, or shows both fleets of a saved
+/// game.
+fn replay(file: &Path, display: &DisplayOptions) {
+    let game = match read_any_game_file(file) {
+        GameFile::Record(record) => return play_back(&record, display),
+        GameFile::Game(game, _) => game,
+    };
+
+    if display.accessible {
+        for player in PLAYERS {
+            println!("Player {}'s fleet:", player);
+            for line in accessible::describe_fleet(&PlayerView::new(&game, player)) {
+                println!("{}", line);
+            }
+        }
+        println!("{}", accessible::describe_result(&game));
+        return;
+    }
+
+    let (mut io, _raw_mode) = set_up_terminal(display);
+    display_last_scene(&mut io, &game, &[]);
+}
+
+fn play_back(record: &Record, display: &DisplayOptions) {
+    let title = format!(
+        "Alpha ({}) against Beta ({}), {} rules, {}.",
+        record.alpha, record.beta, record.game.rules.name, record.date
+    );
+    let shots = record.game.moves.len();
+    let describe = |number: usize, mv: Move, shot: Shot| {
+        format!(
+            "Shot {} of {}: Player {} {}",
+            number + 1,
+            shots,
+            mv.player,
+            accessible::describe_shot(mv.cell.to_board(), shot)
+        )
+    };
+
+    if display.accessible {
+        println!("{}", title);
+        for (number, (mv, shot, _)) in record.positions().enumerate() {
+            println!("{}", describe(number, mv, shot));
+        }
+        println!("{}", accessible::describe_result(&record.game));
+        return;
+    }
+
+    let (mut io, _raw_mode) = set_up_terminal(display);
+    io.clear();
+    io.wait_for_enter(&title);
+    for (number, (mv, shot, game)) in record.positions().enumerate() {
+        let views = PLAYERS.map(|player| PlayerView::new(&game, player));
+        let text = describe(number, mv, shot);
+        if !io.display_replay_step([&views[0], &views[1]], mv.cell.to_board(), &text) {
+            break;
+        }
+    }
+    display_last_scene(&mut io, &record.game, &[]);
+}
+
+/// Prints how far each player got in a game, and a review of every shot
+/// fired.
+fn analyze(file: &Path) {
+    let (game, turn) = read_game_file(file);
+
+    for player in PLAYERS {
+        let view = PlayerView::new(&game, player);
+        let sunk = (0..SHIPS_COUNT)
+            .filter(|&layer| view.is_enemy_sunk(layer))
+            .count();
+        println!(
+            "Player {}: {} cells known, {} hits, {} of {} ships sunk.",
+            player,
+            view.shots.count_ones(),
+            view.hits.count_ones(),
+            sunk,
+            SHIPS_COUNT
+        );
+    }
+
+    match game.get_winner() {
+        Some(_) => println!("{}", accessible::describe_result(&game)),
+        None => println!("Player {} shoots next.", turn),
+    }
+
+    let review = Review::new(&game);
+    if review.shots.is_empty() {
+        return;
+    }
+    println!();
+    for line in review.summary() {
+        println!("{}", line);
+    }
+    println!();
+    for i in 0..review.shots.len() {
+        println!("{}", review.describe_shot(i));
+    }
+}
+
+/// Prints a game as JSON or text boards, by default the one in the save
+/// slot.
+fn export(file: Option<&Path>, text: bool) {
+    let (game, turn) = match file {
+        Some(file) => read_game_file(file),
+        None => match save::read_saved_game() {
+            Ok(Some(saved)) => saved,
+            Ok(None) => fail("there is no saved game"),
+            Err(err) => fail(&format!("cannot load the saved game: {}", err)),
+        },
+    };
+
+    match text {
+        true => print!("{}", GameText(&game)),
+        false => println!("{}", export::game_to_json(&game, turn)),
+    }
+}
+
+/// Puts a game in JSON in the save slot, so `play` offers to resume it.
+fn import(file: &Path) {
+    let (game, turn) = read_game_file(file);
+    if game.is_over() {
+        fail(&format!("{}: the game is over already", file.display()));
+    }
+
+    match save::save_game(&game, turn) {
+        Ok(path) => println!(
+            "Saved to {}. Resume it with battleship play.",
+            path.display()
+        ),
+        Err(err) => fail(&format!("cannot save the game: {}", err)),
+    }
+}
+
+/// Prints a table of the players' statistics.
+fn print_stats(name: Option<&str>) {
+    let stats = match Stats::load() {
+        Ok(stats) => stats,
+        Err(err) => fail(&format!("{}: {}", stats::STATS_FILE, err)),
+    };
+    let profiles: Vec<_> = match name {
+        Some(name) => match stats.profile(name) {
+            Some(profile) => vec![(name, profile)],
+            None => fail(&format!("`{}` has not finished a game", name)),
+        },
+        None => stats
+            .profiles
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile))
+            .collect(),
+    };
+    if profiles.is_empty() {
+        println!("Nobody has finished a game yet.");
+        return;
+    }
+
+    let width = profiles
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("player".len());
+    println!(
+        "{:width$}  games   wins  accuracy  shots/win  streak",
+        "player"
+    );
+    for (name, profile) in profiles {
+        let accuracy = profile.accuracy().map_or(String::from("-"), |accuracy| {
+            format!("{:.0}%", accuracy * 100.0)
+        });
+        let average = profile
+            .average_shots_to_win()
+            .map_or(String::from("-"), |average| format!("{:.1}", average));
+        println!(
+            "{:width$}  {:>5}  {:>5}  {:>8}  {:>9}  {:>6}",
+            name, profile.games, profile.wins, accuracy, average, profile.longest_streak
+        );
+    }
 }
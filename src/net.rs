@@ -0,0 +1,492 @@
+//! Playing over the network. The host keeps the whole game and sends the
+//! player who joined only their [`PlayerView`], so the joining side never
+//! learns where the host's ships are before the game ends.
+//!
+//! Messages are sent as frames: a big endian `u16` length, the message and
+//! a CRC-32 of the message. A message starts with its kind, and boards,
+//! ships and cells are written like in [`codec`] records:
+//!
+//! ```text
+//! 0 hello    magic "BS", protocol version, rules index or ff, UTF-8 name
+//! 1 fleet    5 ships
+//! 2 view     player, 5 ships, shots, hits, incoming, 5 sunk ships, winner or ff
+//! 3 shot     player, cell, 0 miss, 1 hit or 2 + layer of the sunk ship
+//! 4 turn     the joining player may shoot
+//! 5 shoot    cell
+//! 6 concede
+//! 7 over     winner, 1 if conceded, the host's 5 ships
+//! 8 error    UTF-8 text
+//! ```
+//!
+//! The host greets with its rules and the joining player answers without.
+//! Both say the name of their player, for [`stats`](crate::stats) and
+//! [`rating`](crate::rating).
+
+use std::io::{self, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+
+use crate::codec::{self, player_from_byte, player_to_byte, CodecError, Reader, Writer, MAGIC};
+use crate::coord::Coord;
+use crate::game::{Player, Shot, SHIPS_COUNT, SHIP_SIZES};
+use crate::rules::{Rules, RULES};
+use crate::stats::is_valid_name;
+use crate::term::{self, Key};
+use crate::view::PlayerView;
+
+pub const DEFAULT_PORT: u16 = 7777;
+const PROTOCOL: u8 = 3;
+/// Longest message. Error texts are cut to fit.
+const MAX_MESSAGE: usize = 256;
+const NONE: u8 = 0xff;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Rules are only sent by the host.
+    Hello {
+        rules: Option<Rules>,
+        name: String,
+    },
+    Fleet([u128; SHIPS_COUNT]),
+    View(PlayerView),
+    Shot {
+        player: Player,
+        shoot: u128,
+        shot: Shot,
+    },
+    Turn,
+    Shoot(u128),
+    Concede,
+    Over {
+        winner: Player,
+        conceded: bool,
+        fleet: [u128; SHIPS_COUNT],
+    },
+    Error(String),
+}
+
+impl Message {
+    /// The message as a frame ready to be sent.
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut buf = [0; 2 + MAX_MESSAGE + 4];
+        let mut writer = Writer::new(&mut buf[2..]);
+        self.write(&mut writer).map_err(invalid)?;
+        let len = writer.finish().map_err(invalid)? - 4;
+
+        buf[..2].copy_from_slice(&(len as u16).to_be_bytes());
+        Ok(buf[..2 + len + 4].to_vec())
+    }
+
+    fn write(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        match self {
+            Message::Hello { rules, name } => {
+                writer.byte(0)?;
+                writer.bytes(&MAGIC)?;
+                writer.byte(PROTOCOL)?;
+                let rules = rules.and_then(|rules| RULES.iter().position(|r| *r == rules));
+                writer.byte(rules.map_or(NONE, |index| index as u8))?;
+                if !is_valid_name(name) {
+                    return Err(CodecError::Invalid("bad player name"));
+                }
+                writer.bytes(name.as_bytes())?;
+            }
+            Message::Fleet(ships) => {
+                writer.byte(1)?;
+                write_ships(writer, ships)?;
+            }
+            Message::View(view) => {
+                writer.byte(2)?;
+                writer.byte(player_to_byte(view.player))?;
+                write_ships(writer, &view.ships)?;
+                writer.board(view.shots)?;
+                writer.board(view.hits)?;
+                writer.board(view.incoming)?;
+                write_ships(writer, &view.sunk_enemy)?;
+                writer.byte(view.winner.map_or(NONE, player_to_byte))?;
+            }
+            Message::Shot {
+                player,
+                shoot,
+                shot,
+            } => {
+                writer.byte(3)?;
+                writer.byte(player_to_byte(*player))?;
+                writer.byte(cell_to_byte(*shoot)?)?;
+                writer.byte(match shot {
+                    Shot::Miss => 0,
+                    Shot::Hit => 1,
+                    Shot::Sunk(layer) => 2 + *layer as u8,
+                })?;
+            }
+            Message::Turn => writer.byte(4)?,
+            Message::Shoot(shoot) => {
+                writer.byte(5)?;
+                writer.byte(cell_to_byte(*shoot)?)?;
+            }
+            Message::Concede => writer.byte(6)?,
+            Message::Over {
+                winner,
+                conceded,
+                fleet,
+            } => {
+                writer.byte(7)?;
+                writer.byte(player_to_byte(*winner))?;
+                writer.byte(u8::from(*conceded))?;
+                write_ships(writer, fleet)?;
+            }
+            Message::Error(text) => {
+                writer.byte(8)?;
+                let mut end = text.len().min(MAX_MESSAGE - 1);
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                writer.bytes(&text.as_bytes()[..end])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a whole frame, as made by [`Message::encode`].
+    pub fn decode(frame: &[u8]) -> io::Result<Message> {
+        let len = match frame {
+            [high, low, ..] => usize::from(u16::from_be_bytes([*high, *low])),
+            _ => return Err(invalid(CodecError::Truncated)),
+        };
+        if frame.len() != 2 + len + 4 {
+            return Err(invalid(CodecError::Truncated));
+        }
+        codec::verify_checksum(&frame[2..]).map_err(invalid)?;
+
+        Message::read(&mut Reader::new(&frame[2..2 + len]), len).map_err(invalid)
+    }
+
+    fn read(reader: &mut Reader, len: usize) -> Result<Message, CodecError> {
+        let message = match reader.byte()? {
+            0 => {
+                if reader.take(MAGIC.len())? != MAGIC {
+                    return Err(CodecError::Magic);
+                }
+                match reader.byte()? {
+                    PROTOCOL => {}
+                    version => return Err(CodecError::Version(version)),
+                }
+                let rules = match reader.byte()? {
+                    NONE => None,
+                    index => Some(
+                        *RULES
+                            .get(usize::from(index))
+                            .ok_or(CodecError::Invalid("unknown rules"))?,
+                    ),
+                };
+                let name = std::str::from_utf8(reader.take(len - reader.position())?)
+                    .ok()
+                    .filter(|name| is_valid_name(name))
+                    .ok_or(CodecError::Invalid("bad player name"))?;
+                Message::Hello {
+                    rules,
+                    name: String::from(name),
+                }
+            }
+            1 => Message::Fleet(read_ships(reader)?),
+            2 => Message::View(PlayerView {
+                player: player_from_byte(reader.byte()?)?,
+                ships: read_ships(reader)?,
+                shots: reader.board()?,
+                hits: reader.board()?,
+                incoming: reader.board()?,
+                sunk_enemy: read_ships(reader)?,
+                winner: match reader.byte()? {
+                    NONE => None,
+                    byte => Some(player_from_byte(byte)?),
+                },
+            }),
+            3 => Message::Shot {
+                player: player_from_byte(reader.byte()?)?,
+                shoot: cell_from_byte(reader.byte()?)?,
+                shot: match reader.byte()? {
+                    0 => Shot::Miss,
+                    1 => Shot::Hit,
+                    byte if usize::from(byte - 2) < SHIPS_COUNT => {
+                        Shot::Sunk(usize::from(byte - 2))
+                    }
+                    _ => return Err(CodecError::Invalid("unknown shot result")),
+                },
+            },
+            4 => Message::Turn,
+            5 => Message::Shoot(cell_from_byte(reader.byte()?)?),
+            6 => Message::Concede,
+            7 => Message::Over {
+                winner: player_from_byte(reader.byte()?)?,
+                conceded: reader.byte()? != 0,
+                fleet: read_ships(reader)?,
+            },
+            8 => {
+                let text = reader.take(len - reader.position())?;
+                let text = std::str::from_utf8(text)
+                    .map_err(|_| CodecError::Invalid("error text is not UTF-8"))?;
+                Message::Error(String::from(text))
+            }
+            _ => return Err(CodecError::Invalid("unknown message")),
+        };
+
+        match reader.position() == len {
+            true => Ok(message),
+            false => Err(CodecError::Invalid("message is too long")),
+        }
+    }
+}
+
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Connection> {
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        self.writer.write_all(&message.encode()?)
+    }
+
+    /// Waits for the next message. Ctrl-C still works while waiting.
+    pub fn receive(&mut self) -> io::Result<Message> {
+        if self.reader.buffer().is_empty() {
+            wait_readable(self.writer.as_raw_fd())?;
+        }
+
+        let mut frame = vec![0; 2];
+        self.reader
+            .read_exact(&mut frame)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::UnexpectedEof => {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "the other player left")
+                }
+                _ => err,
+            })?;
+        let len = usize::from(u16::from_be_bytes([frame[0], frame[1]]));
+        frame.resize(2 + len + 4, 0);
+        self.reader.read_exact(&mut frame[2..])?;
+
+        match Message::decode(&frame)? {
+            Message::Error(text) => Err(io::Error::other(text)),
+            message => Ok(message),
+        }
+    }
+}
+
+/// Waits for a player to join on the address, and tells them the rules.
+/// Returns the connection and the name of the player who joined.
+pub fn host(address: &str, rules: Rules, name: &str) -> io::Result<(Connection, String)> {
+    let listener = TcpListener::bind(address)?;
+    wait_readable(listener.as_raw_fd())?;
+    let (stream, _) = listener.accept()?;
+
+    let mut connection = Connection::new(stream)?;
+    connection.send(&Message::Hello {
+        rules: Some(rules),
+        name: String::from(name),
+    })?;
+    match connection.receive()? {
+        Message::Hello { rules: None, name } => Ok((connection, name)),
+        _ => Err(io::Error::other(
+            "the other side does not speak the protocol",
+        )),
+    }
+}
+
+/// Joins a hosted game. Returns the connection, the host's rules and the
+/// host's name.
+pub fn join(address: &str, name: &str) -> io::Result<(Connection, Rules, String)> {
+    let mut connection = Connection::new(TcpStream::connect(address)?)?;
+
+    match connection.receive()? {
+        Message::Hello {
+            rules: Some(rules),
+            name: host,
+        } => {
+            connection.send(&Message::Hello {
+                rules: None,
+                name: String::from(name),
+            })?;
+            Ok((connection, rules, host))
+        }
+        _ => Err(io::Error::other(
+            "the other side does not speak the protocol",
+        )),
+    }
+}
+
+/// Adds the default port to an address without one.
+pub fn with_port(address: &str) -> String {
+    match address.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => String::from(address),
+        _ => format!("{}:{}", address, DEFAULT_PORT),
+    }
+}
+
+/// Blocks until the descriptor can be read. Keys pressed meanwhile are
+/// dropped, except Ctrl-C, which quits as usual.
+fn wait_readable(fd: i32) -> io::Result<()> {
+    loop {
+        let mut fds = [
+            libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: 0,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        if unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) } < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+
+        if fds[1].revents & libc::POLLIN != 0 && term::read_key() == Key::CtrlC {
+            term::interrupt();
+        }
+        if fds[0].revents != 0 {
+            return Ok(());
+        }
+    }
+}
+
+fn write_ships(writer: &mut Writer, ships: &[u128; SHIPS_COUNT]) -> Result<(), CodecError> {
+    for (ship, size) in ships.iter().zip(SHIP_SIZES) {
+        writer.ship(*ship, size)?;
+    }
+    Ok(())
+}
+
+fn read_ships(reader: &mut Reader) -> Result<[u128; SHIPS_COUNT], CodecError> {
+    let mut ships = [0; SHIPS_COUNT];
+    for (ship, size) in ships.iter_mut().zip(SHIP_SIZES) {
+        *ship = reader.ship(size)?;
+    }
+    Ok(ships)
+}
+
+fn cell_to_byte(board: u128) -> Result<u8, CodecError> {
+    match Coord::from_board(board) {
+        Some(cell) if cell.to_board() == board => Ok(cell.index() as u8),
+        _ => Err(CodecError::Invalid("a shot is one cell")),
+    }
+}
+
+fn cell_from_byte(byte: u8) -> Result<u128, CodecError> {
+    Coord::from_index(usize::from(byte))
+        .map(Coord::to_board)
+        .ok_or(CodecError::Invalid("cell outside the board"))
+}
+
+fn invalid(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::{constants::CELL, game::Game, rng::Rng};
+
+    #[test]
+    fn messages_round_trip() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(1));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(2));
+        let shoot = Coord::new(4, 2).to_board();
+        let shot = game.shoot(Player::Alpha, shoot).unwrap();
+
+        let messages = [
+            Message::Hello {
+                rules: None,
+                name: String::from("bob"),
+            },
+            Message::Hello {
+                rules: Some(Rules::SALVO),
+                name: "é".repeat(16),
+            },
+            Message::Fleet(game.ships_beta),
+            Message::View(PlayerView::new(&game, Player::Beta)),
+            Message::Shot {
+                player: Player::Alpha,
+                shoot,
+                shot,
+            },
+            Message::Shot {
+                player: Player::Beta,
+                shoot,
+                shot: Shot::Sunk(3),
+            },
+            Message::Turn,
+            Message::Shoot(shoot),
+            Message::Concede,
+            Message::Over {
+                winner: Player::Alpha,
+                conceded: true,
+                fleet: game.ships_alpha,
+            },
+            Message::Error(String::from("no room left")),
+        ];
+
+        for message in messages {
+            let frame = message.encode().unwrap();
+            assert_eq!(Message::decode(&frame).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_messages() {
+        let frame = Message::Shoot(CELL).encode().unwrap();
+        assert!(Message::decode(&frame[..frame.len() - 1]).is_err());
+
+        let mut damaged = frame.clone();
+        damaged[3] = 100;
+        assert!(Message::decode(&damaged).is_err());
+
+        let nameless = [0, b'B', b'S', PROTOCOL, NONE];
+        for payload in [
+            &[9][..],
+            &[5, 100],
+            &[3, 0, 0, 7],
+            &[4, 0],
+            &[8, 0xff],
+            &nameless,
+        ] {
+            let mut frame = (payload.len() as u16).to_be_bytes().to_vec();
+            frame.extend(payload);
+            frame.extend(codec::crc32(payload).to_be_bytes());
+            assert!(Message::decode(&frame).is_err(), "{:?}", payload);
+        }
+
+        let hello = Message::Hello {
+            rules: None,
+            name: String::from("two words"),
+        };
+        assert!(hello.encode().is_err());
+
+        let long = Message::Error("é".repeat(MAX_MESSAGE)).encode().unwrap();
+        assert!(long.len() <= 2 + MAX_MESSAGE + 4);
+        assert!(matches!(Message::decode(&long), Ok(Message::Error(_))));
+    }
+
+    #[test]
+    fn default_port() {
+        assert_eq!(with_port("example.org"), "example.org:7777");
+        assert_eq!(with_port("10.0.0.1:9000"), "10.0.0.1:9000");
+        assert_eq!(with_port("[::1]"), "[::1]:7777");
+    }
+}
@@ -0,0 +1,234 @@
+//! Elo ratings of every player, people and computer strategies alike,
+//! kept in [`RATINGS_FILE`] in the data directory. Players are known by
+//! the same names as in [`stats`](crate::stats).
+//!
+//! Everybody starts at [`INITIAL_RATING`]. After a game the winner takes
+//! points from the loser, more when the winner was expected to lose:
+//!
+//! ```text
+//! # name rating games
+//! alice 1532.4 12
+//! hunt 1467.6 12
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::config::{data_dir, ConfigError};
+use crate::game::Player;
+
+pub const RATINGS_FILE: &str = "ratings.txt";
+pub const INITIAL_RATING: f64 = 1500.0;
+/// Most points a game can move.
+pub const K_FACTOR: f64 = 32.0;
+
+const HEADER: &str = "# name rating games";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    pub games: u64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+/// Chance that a player rated `rating` beats one rated `other`.
+pub fn expected_score(rating: f64, other: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((other - rating) / 400.0))
+}
+
+/// The ratings of all players, in the order they first played.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Ratings {
+    pub players: Vec<(String, Rating)>,
+}
+
+impl Ratings {
+    /// The rating of the player, who may not have played yet.
+    pub fn rating(&self, name: &str) -> Rating {
+        self.players
+            .iter()
+            .find(|(other, _)| other == name)
+            .map_or(Rating::default(), |(_, rating)| *rating)
+    }
+
+    fn rating_mut(&mut self, name: &str) -> &mut Rating {
+        let i = match self.players.iter().position(|(other, _)| other == name) {
+            Some(i) => i,
+            None => {
+                self.players.push((String::from(name), Rating::default()));
+                self.players.len() - 1
+            }
+        };
+        &mut self.players[i].1
+    }
+
+    /// Rates a finished game between the players named, Alpha first.
+    /// Returns how many points each of them won or lost.
+    pub fn add_game(&mut self, names: [&str; 2], winner: Player) -> [f64; 2] {
+        // Somebody playing themselves, like a strategy against itself,
+        // neither gains nor loses
+        if names[0] == names[1] {
+            self.rating_mut(names[0]).games += 1;
+            return [0.0; 2];
+        }
+
+        let ratings = names.map(|name| self.rating(name).rating);
+        let score = match winner {
+            Player::Alpha => 1.0,
+            Player::Beta => 0.0,
+        };
+        let change = K_FACTOR * (score - expected_score(ratings[0], ratings[1]));
+        for (name, change) in names.into_iter().zip([change, -change]) {
+            let rating = self.rating_mut(name);
+            rating.rating += change;
+            rating.games += 1;
+        }
+        [change, -change]
+    }
+
+    /// Players from the highest rating down.
+    pub fn leaderboard(&self) -> Vec<(&str, Rating)> {
+        let mut players: Vec<_> = self
+            .players
+            .iter()
+            .map(|(name, rating)| (name.as_str(), *rating))
+            .collect();
+        players.sort_by(|(_, a), (_, b)| b.rating.total_cmp(&a.rating));
+        players
+    }
+
+    pub fn parse(text: &str) -> Result<Ratings, ConfigError> {
+        let mut ratings = Ratings::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let [name, rating, games] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(ConfigError::syntax(
+                    line_number,
+                    "expected a name, a rating and games",
+                ));
+            };
+            let rating = rating
+                .parse::<f64>()
+                .ok()
+                .filter(|rating| rating.is_finite())
+                .ok_or_else(|| ConfigError::syntax(line_number, "the rating is not a number"))?;
+            let games = games
+                .parse()
+                .map_err(|_| ConfigError::syntax(line_number, "games is not a number"))?;
+            if ratings.players.iter().any(|(other, _)| other == name) {
+                return Err(ConfigError::syntax(line_number, "the player appears twice"));
+            }
+
+            ratings
+                .players
+                .push((String::from(name), Rating { rating, games }));
+        }
+
+        Ok(ratings)
+    }
+
+    /// Reads the ratings file. Without one nobody has played yet.
+    pub fn load() -> Result<Ratings, ConfigError> {
+        let Some(path) = ratings_path() else {
+            return Ok(Ratings::default());
+        };
+
+        match fs::read_to_string(path) {
+            Ok(text) => Ratings::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Ratings::default()),
+            Err(err) => Err(ConfigError::Io(err)),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = ratings_path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory")
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+}
+
+/// The ratings file contents.
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (name, rating) in &self.players {
+            writeln!(f, "{} {} {}", name, rating.rating, rating.games)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn ratings_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(RATINGS_FILE))
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn winners_take_points() {
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+        assert!((expected_score(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+
+        let mut ratings = Ratings::default();
+        assert_eq!(
+            ratings.add_game(["alice", "hunt"], Player::Alpha),
+            [16.0, -16.0]
+        );
+        assert_eq!(ratings.rating("alice").rating, 1516.0);
+        assert_eq!(ratings.rating("hunt").rating, 1484.0);
+        assert_eq!(ratings.rating("bob"), Rating::default());
+
+        // An upset moves more points than the expected result
+        let [expected, _] = ratings.clone().add_game(["alice", "hunt"], Player::Alpha);
+        let [upset, _] = ratings.add_game(["hunt", "alice"], Player::Alpha);
+        assert!(upset > expected);
+        assert_eq!(ratings.rating("alice").games, 2);
+
+        assert_eq!(ratings.add_game(["hunt", "hunt"], Player::Beta), [0.0; 2]);
+        assert_eq!(ratings.rating("hunt").games, 3);
+
+        let board: Vec<&str> = ratings
+            .leaderboard()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(board, ["hunt", "alice"]);
+    }
+
+    #[test]
+    fn ratings_round_trip() {
+        let mut ratings = Ratings::default();
+        ratings.add_game(["alice", "random"], Player::Alpha);
+        ratings.add_game(["bob", "alice"], Player::Beta);
+
+        let text = ratings.to_string();
+        assert!(text.starts_with(HEADER));
+        assert_eq!(Ratings::parse(&text).unwrap(), ratings);
+
+        for text in ["alice 1500", "alice x 1", "alice inf 1", "a 1 1\na 1 1"] {
+            assert!(Ratings::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,572 @@
+//! Game records in text, like chess has PGN. A record has headers, both
+//! fleets and every shot with what it did:
+//!
+//! ```text
+//! [Alpha "human"]
+//! [Beta "hunt"]
+//! [Date "2026-10-18"]
+//! [Rules "classic"]
+//! [Seed "42"]
+//! [Result "alpha"]
+//! [Termination "sunk"]
+//!
+//! alpha carrier A1-E1
+//! alpha battleship C3-C6
+//! ...
+//! beta patrol boat J9-J10
+//!
+//! 1. alpha C5 miss
+//! 2. beta A1 hit
+//! 3. beta B1 sunk patrol boat
+//! ```
+//!
+//! `Result` is the winner, or `*` for a game that is not over, and
+//! `Termination` says whether the loser's fleet was `sunk` or they
+//! `conceded`; it is left out of unfinished games. `Seed` is optional.
+//! Reading a record plays it again, so a record whose shots do not lead
+//! to the outcomes and the result written down is refused.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::data_dir;
+use crate::coord::Coord;
+use crate::game::{ship_at, Game, Player, Shot, PLAYERS, SHIP_NAMES, SHIP_SIZES};
+use crate::log::Move;
+use crate::rules::Rules;
+
+/// Directory in [`data_dir`] that finished games are recorded in.
+pub const RECORDS_DIR: &str = "games";
+pub const RECORD_EXTENSION: &str = "bsr";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Who played each side, like `human` or a strategy name.
+    pub alpha: String,
+    pub beta: String,
+    /// `YYYY-MM-DD`, in UTC.
+    pub date: String,
+    pub seed: Option<u64>,
+    /// The game with its fleets and moves. The rest of it follows from
+    /// them.
+    pub game: Game,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl Record {
+    /// The game as it was after each move, starting with no shots.
+    pub fn positions(&self) -> impl Iterator<Item = (Move, Shot, Game)> + '_ {
+        self.game.replay()
+    }
+}
+
+/// The record in text, see the module documentation.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let game = &self.game;
+        writeln!(f, "[Alpha \"{}\"]", escape(&self.alpha))?;
+        writeln!(f, "[Beta \"{}\"]", escape(&self.beta))?;
+        writeln!(f, "[Date \"{}\"]", escape(&self.date))?;
+        writeln!(f, "[Rules \"{}\"]", game.rules.name)?;
+        if let Some(seed) = self.seed {
+            writeln!(f, "[Seed \"{}\"]", seed)?;
+        }
+        match game.get_winner() {
+            Some(winner) => {
+                writeln!(f, "[Result \"{}\"]", winner.name())?;
+                let termination = match game.conceded {
+                    Some(_) => "conceded",
+                    None => "sunk",
+                };
+                writeln!(f, "[Termination \"{}\"]", termination)?;
+            }
+            None => writeln!(f, "[Result \"*\"]")?,
+        }
+
+        writeln!(f)?;
+        for player in PLAYERS {
+            for (layer, ship) in game.get_ships(player).into_iter().enumerate() {
+                if ship == 0 {
+                    continue;
+                }
+                let first = Coord::from_board(ship).expect("a ship has cells");
+                let last = Coord::all()
+                    .filter(|cell| ship & cell.to_board() != 0)
+                    .last()
+                    .expect("a ship has cells");
+                writeln!(
+                    f,
+                    "{} {} {}-{}",
+                    player.name(),
+                    SHIP_NAMES[layer],
+                    first,
+                    last
+                )?;
+            }
+        }
+
+        if !game.moves.is_empty() {
+            writeln!(f)?;
+        }
+        for (number, (mv, shot, _)) in self.positions().enumerate() {
+            let outcome = match shot {
+                Shot::Miss => String::from("miss"),
+                Shot::Hit => String::from("hit"),
+                Shot::Sunk(layer) => format!("sunk {}", SHIP_NAMES[layer]),
+            };
+            writeln!(
+                f,
+                "{}. {} {} {}",
+                number + 1,
+                mv.player.name(),
+                mv.cell,
+                outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a record and checks it by playing it again.
+pub fn parse(text: &str) -> Result<Record, RecordError> {
+    let mut headers = Headers::default();
+    let mut game = Game::default();
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+
+    // Headers come first, up to the first line that is not one
+    let mut body = None;
+    for (line, text) in lines.by_ref() {
+        if text.is_empty() {
+            continue;
+        }
+        match text
+            .strip_prefix('[')
+            .and_then(|text| text.strip_suffix(']'))
+        {
+            Some(header) => headers.add(line, header)?,
+            None => {
+                body = Some((line, text));
+                break;
+            }
+        }
+    }
+
+    let alpha = headers.take("Alpha")?;
+    let beta = headers.take("Beta")?;
+    let date = headers.take("Date")?;
+    let (rules_line, rules) = headers.take_with_line("Rules")?;
+    game.rules = Rules::by_name(&rules).ok_or(error(rules_line, "unknown rules"))?;
+    let seed = match headers.take_optional("Seed") {
+        Some((line, seed)) => Some(
+            seed.parse()
+                .map_err(|_| error(line, "the seed is a number"))?,
+        ),
+        None => None,
+    };
+    let (result_line, result) = headers.take_with_line("Result")?;
+    let result = match result.as_str() {
+        "*" => None,
+        name => Some(
+            name.parse::<Player>()
+                .map_err(|_| error(result_line, "unknown result"))?,
+        ),
+    };
+    let termination = headers.take_optional("Termination");
+    headers.finish()?;
+
+    let mut number = 0;
+    let mut last_line = result_line;
+    for (line, text) in body.into_iter().chain(lines) {
+        last_line = line;
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some((number_text, shot)) = text.split_once(". ") {
+            number += 1;
+            if number_text.parse() != Ok(number) {
+                return Err(error(line, format!("expected shot {}", number)));
+            }
+            read_shot(&mut game, line, shot)?;
+        } else if number == 0 {
+            read_ship(&mut game, line, text)?;
+        } else {
+            return Err(error(line, "ships come before the shots"));
+        }
+    }
+
+    match (result, termination) {
+        (None, None) => {}
+        (None, Some((line, _))) => {
+            return Err(error(line, "an unfinished game has no termination"))
+        }
+        (Some(_), None) => return Err(error(result_line, "missing header Termination")),
+        (Some(winner), Some((line, termination))) => match termination.as_str() {
+            "sunk" => {}
+            "conceded" if !game.is_over() => game.concede(winner.other()),
+            "conceded" => return Err(error(line, "the game was already over")),
+            _ => return Err(error(line, "the termination is `sunk` or `conceded`")),
+        },
+    }
+    if game.get_winner() != result {
+        return Err(error(last_line, "the shots do not lead to the result"));
+    }
+
+    Ok(Record {
+        alpha,
+        beta,
+        date,
+        seed,
+        game,
+    })
+}
+
+/// `alpha carrier A1-E1`
+fn read_ship(game: &mut Game, line: usize, text: &str) -> Result<(), RecordError> {
+    let (player, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let player = player
+        .parse::<Player>()
+        .map_err(|_| error(line, "expected a ship or a shot"))?;
+    let (name, cells) = rest
+        .rsplit_once(' ')
+        .ok_or(error(line, "expected a ship like `alpha carrier A1-E1`"))?;
+    let layer = SHIP_NAMES
+        .iter()
+        .position(|&ship| ship == name)
+        .ok_or(error(line, "unknown ship"))?;
+
+    let (start, end) = cells
+        .split_once('-')
+        .ok_or(error(line, "expected the first and last cell, like A1-E1"))?;
+    let start: Coord = start
+        .parse()
+        .map_err(|err| error(line, format!("{}", err)))?;
+    let end: Coord = end.parse().map_err(|err| error(line, format!("{}", err)))?;
+    let size = SHIP_SIZES[layer];
+    let down = start.x == end.x;
+    let last = match down {
+        true => Coord::new(start.x, start.y + size - 1),
+        false => Coord::new(start.x + size - 1, start.y),
+    };
+    let ship = ship_at(start, size, down)
+        .filter(|_| last == end)
+        .ok_or(error(
+            line,
+            format!("a {} is {} cells across or down", name, size),
+        ))?;
+
+    if game.get_ships(player)[layer] != 0 {
+        return Err(error(line, "the ship appears twice"));
+    }
+    game.add_ship(player, ship, layer)
+        .map_err(|err| error(line, err.to_string()))
+}
+
+/// `alpha C5 miss`, after the number.
+fn read_shot(game: &mut Game, line: usize, text: &str) -> Result<(), RecordError> {
+    let mut words = text.splitn(3, ' ');
+    let player = words
+        .next()
+        .and_then(|word| word.parse().ok())
+        .ok_or(error(line, "expected a shot like `1. alpha C5 miss`"))?;
+    let cell: Coord = words
+        .next()
+        .unwrap_or("")
+        .parse()
+        .map_err(|err| error(line, format!("{}", err)))?;
+    let shot = game
+        .play_move(Move { player, cell })
+        .map_err(|err| error(line, err.to_string()))?;
+
+    let written = words.next().unwrap_or("");
+    let outcome = match shot {
+        Shot::Miss => String::from("miss"),
+        Shot::Hit => String::from("hit"),
+        Shot::Sunk(layer) => format!("sunk {}", SHIP_NAMES[layer]),
+    };
+    match written == outcome {
+        true => Ok(()),
+        false => Err(error(line, format!("the shot was a {}", outcome))),
+    }
+}
+
+/// Headers in the order they were read, each taken once.
+#[derive(Default)]
+struct Headers {
+    headers: Vec<(usize, String, String)>,
+    /// Line of the last header, for missing ones.
+    last_line: usize,
+}
+
+impl Headers {
+    /// `Name "value"`, inside the brackets.
+    fn add(&mut self, line: usize, header: &str) -> Result<(), RecordError> {
+        let value_error = || error(line, "expected a header like [Name \"value\"]");
+        let (name, value) = header.split_once(' ').ok_or_else(value_error)?;
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .ok_or_else(value_error)?;
+        let value = unescape(value).ok_or_else(value_error)?;
+
+        if self.headers.iter().any(|(_, other, _)| other == name) {
+            return Err(error(line, format!("header {} appears twice", name)));
+        }
+        self.headers.push((line, String::from(name), value));
+        self.last_line = line;
+        Ok(())
+    }
+
+    fn take_optional(&mut self, name: &str) -> Option<(usize, String)> {
+        let i = self
+            .headers
+            .iter()
+            .position(|(_, other, _)| other == name)?;
+        let (line, _, value) = self.headers.remove(i);
+        Some((line, value))
+    }
+
+    fn take_with_line(&mut self, name: &str) -> Result<(usize, String), RecordError> {
+        self.take_optional(name)
+            .ok_or_else(|| error(self.last_line, format!("missing header {}", name)))
+    }
+
+    fn take(&mut self, name: &str) -> Result<String, RecordError> {
+        Ok(self.take_with_line(name)?.1)
+    }
+
+    fn finish(self) -> Result<(), RecordError> {
+        match self.headers.first() {
+            Some((line, name, _)) => Err(error(*line, format!("unknown header {}", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(chars.next().filter(|c| matches!(c, '\\' | '"'))?),
+            '"' => return None,
+            c => text.push(c),
+        }
+    }
+    Some(text)
+}
+
+fn error(line: usize, message: impl Into<String>) -> RecordError {
+    RecordError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Writes the record to a new file in [`RECORDS_DIR`], named after the
+/// time it was written.
+pub fn save_record(record: &Record) -> io::Result<PathBuf> {
+    let dir = data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory"))?
+        .join(RECORDS_DIR);
+    fs::create_dir_all(&dir)?;
+
+    let (date, time) = now();
+    let name = format!("{}-{}", date, time.replace(':', ""));
+    for attempt in 0.. {
+        let file = match attempt {
+            0 => format!("{}.{}", name, RECORD_EXTENSION),
+            n => format!("{}-{}.{}", name, n, RECORD_EXTENSION),
+        };
+        let path = dir.join(file);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                io::Write::write_all(&mut file, record.to_string().as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("some name is free")
+}
+
+/// Today's date for the `Date` header.
+pub fn today() -> String {
+    now().0
+}
+
+/// The date and time in UTC, `YYYY-MM-DD` and `HH:MM:SS`.
+fn now() -> (String, String) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}:{:02}:{:02}", time / 3600, time / 60 % 60, time % 60),
+    )
+}
+
+/// Date of the day that many days after 1970-01-01, from Howard Hinnant's
+/// `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::{ai::played, log::MOVES_CAPACITY};
+
+    #[cfg(test)]
+    fn finished_record(seed: u64) -> Record {
+        let game = played(Rules::CLASSIC, seed, MOVES_CAPACITY);
+        Record {
+            alpha: String::from("human"),
+            beta: String::from("hunt \"v2\""),
+            date: String::from("2026-10-18"),
+            seed: Some(seed),
+            game,
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let record = finished_record(4);
+        let text = record.to_string();
+        assert!(text.starts_with("[Alpha \"human\"]\n[Beta \"hunt \\\"v2\\\"\"]\n"));
+        assert!(text.contains("\n1. alpha "));
+        assert_eq!(parse(&text), Ok(record.clone()));
+
+        let mut conceded = record;
+        conceded.game = conceded.game.without_shots();
+        conceded.game.concede(Player::Alpha);
+        conceded.seed = None;
+        let text = conceded.to_string();
+        assert!(text.contains("[Result \"beta\"]\n[Termination \"conceded\"]\n"));
+        assert_eq!(parse(&text), Ok(conceded));
+    }
+
+    #[test]
+    fn refused_shots_are_not_recorded() {
+        let mut record = finished_record(5);
+        record.game = record.game.without_shots();
+        let cell = crate::constants::CELL;
+        record.game.shoot(Player::Alpha, cell).unwrap();
+        record.game.shoot(Player::Beta, cell).unwrap();
+        assert!(record.game.shoot(Player::Alpha, cell).is_err());
+
+        let text = record.to_string();
+        assert!(!text.contains("\n3. "));
+        assert_eq!(parse(&text), Ok(record));
+    }
+
+    #[test]
+    fn positions_follow_the_moves() {
+        let record = finished_record(6);
+        let positions: Vec<_> = record.positions().collect();
+        assert_eq!(positions.len(), record.game.moves.len());
+        assert_eq!(positions.last().unwrap().2, record.game);
+        assert_eq!(positions[0].2.moves.len(), 1);
+    }
+
+    #[test]
+    fn rejects_bad_records() {
+        let text = finished_record(4).to_string();
+        let first_shot = text.lines().find(|line| line.starts_with("1. ")).unwrap();
+        let wrong = match first_shot.ends_with(" miss") {
+            true => first_shot.replace(" miss", " hit"),
+            false => format!("{} miss", first_shot.split_once(" hit").unwrap().0),
+        };
+
+        for (from, to) in [
+            ("[Rules \"classic\"]", "[Rules \"chess\"]"),
+            (
+                "[Rules \"classic\"]",
+                "[Rules \"classic\"]\n[Rules \"classic\"]",
+            ),
+            ("[Rules \"classic\"]", "[Event \"final\"]"),
+            ("[Date \"2026-10-18\"]\n", ""),
+            ("[Seed \"4\"]", "[Seed \"four\"]"),
+            ("[Result \"alpha\"]", "[Result \"beta\"]"),
+            ("[Termination \"sunk\"]", "[Termination \"timeout\"]"),
+            ("[Termination \"sunk\"]\n", ""),
+            ("alpha carrier", "alpha canoe"),
+            ("beta patrol boat ", "beta carrier "),
+            (first_shot, &wrong),
+            ("\n2. ", "\n3. "),
+        ] {
+            let changed = text.replacen(from, to, 1);
+            assert_ne!(changed, text, "{}", from);
+            assert!(parse(&changed).is_err(), "{} -> {}", from, to);
+        }
+
+        let unfinished = text
+            .lines()
+            .take_while(|line| !line.starts_with("10. "))
+            .collect::<Vec<_>>();
+        let unfinished = unfinished.join("\n").replace(
+            "[Result \"alpha\"]\n[Termination \"sunk\"]",
+            "[Result \"*\"]",
+        );
+        assert!(parse(&unfinished).unwrap().game.get_winner().is_none());
+        let err = parse(&unfinished.replace(
+            "[Result \"*\"]",
+            "[Result \"alpha\"]\n[Termination \"sunk\"]",
+        ))
+        .unwrap_err();
+        assert_eq!(err.message, "the shots do not lead to the result");
+
+        let out_of_turn = text.replacen("\n2. beta ", "\n2. alpha ", 1);
+        assert_ne!(out_of_turn, text);
+        let err = parse(&out_of_turn).unwrap_err();
+        assert_eq!(err.message, "it was the other player's turn");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20744), (2026, 10, 18));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+}
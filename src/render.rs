@@ -0,0 +1,217 @@
+use std::io::{self, Write};
+
+use crate::theme::{Color, Style};
+
+/// One terminal cell: a character and the style it is drawn with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    pub ch: char,
+    pub style: Style,
+}
+
+impl Glyph {
+    pub const BLANK: Glyph = Glyph {
+        ch: ' ',
+        style: Style::fg(Color::Default),
+    };
+}
+
+/// A screen worth of glyphs. Rows grow as things are written, so a frame is
+/// only as big as what was drawn on it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    rows: Vec<Vec<Glyph>>,
+    /// Row the next `println` writes to.
+    line: usize,
+}
+
+impl Frame {
+    pub fn get(&self, column: usize, row: usize) -> Glyph {
+        self.rows
+            .get(row)
+            .and_then(|glyphs| glyphs.get(column))
+            .copied()
+            .unwrap_or(Glyph::BLANK)
+    }
+
+    pub fn put(&mut self, column: usize, row: usize, glyph: Glyph) {
+        if self.rows.len() <= row {
+            self.rows.resize(row + 1, Vec::new());
+        }
+        let glyphs = &mut self.rows[row];
+        if glyphs.len() <= column {
+            glyphs.resize(column + 1, Glyph::BLANK);
+        }
+        glyphs[column] = glyph;
+    }
+
+    /// Writes text starting at the given cell. Returns the column after it.
+    pub fn text(&mut self, column: usize, row: usize, text: &str, style: Style) -> usize {
+        let mut column = column;
+        for ch in text.chars() {
+            self.put(column, row, Glyph { ch, style });
+            column += 1;
+        }
+        column
+    }
+
+    /// Writes glyphs starting at the given cell. Returns the column after
+    /// them.
+    pub fn glyphs(&mut self, column: usize, row: usize, glyphs: &[Glyph]) -> usize {
+        for (i, &glyph) in glyphs.iter().enumerate() {
+            self.put(column + i, row, glyph);
+        }
+        column + glyphs.len()
+    }
+
+    /// Row the next `println` writes to.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Moves the `println` position, e.g. past something drawn with `put`.
+    pub fn set_line(&mut self, line: usize) {
+        self.line = line;
+    }
+
+    pub fn println(&mut self, text: &str) {
+        for line in text.split('\n') {
+            self.text(0, self.line, line, Style::default());
+            self.line += 1;
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len().max(self.line)
+    }
+
+    /// Columns up to the last glyph of the widest row.
+    pub fn width(&self) -> usize {
+        (0..self.rows.len())
+            .map(|row| self.row_width(row))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn row_width(&self, row: usize) -> usize {
+        self.rows.get(row).map_or(0, Vec::len)
+    }
+}
+
+/// Draws frames on the terminal, sending only the cells that changed since
+/// the previous frame.
+#[derive(Default)]
+pub struct Renderer {
+    previous: Option<Frame>,
+}
+
+impl Renderer {
+    pub fn present(&mut self, frame: Frame) {
+        let bytes = diff(self.previous.as_ref(), &frame);
+
+        let mut stdout = io::stdout();
+        stdout.write_all(&bytes).unwrap();
+        stdout.flush().unwrap();
+
+        self.previous = Some(frame);
+    }
+
+    /// Forgets what is on the screen, so the next frame is drawn in full.
+    /// The terminal may have moved or dropped text when it was resized.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+}
+
+/// Escape sequences that turn the `previous` frame on the screen into
+/// `next`. Without a previous frame the screen is cleared first.
+pub fn diff(previous: Option<&Frame>, next: &Frame) -> Vec<u8> {
+    let mut out = Vec::new();
+    let empty = Frame::default();
+
+    let previous = match previous {
+        Some(previous) => previous,
+        None => {
+            out.extend_from_slice(b"\x1b[0m\x1b[2J");
+            &empty
+        }
+    };
+
+    // Where the terminal cursor is and which style is active
+    let mut cursor = None;
+    let mut style = Style::default();
+
+    let height = previous.height().max(next.height());
+    for row in 0..height {
+        let width = previous.row_width(row).max(next.row_width(row));
+        for column in 0..width {
+            let glyph = next.get(column, row);
+            if glyph == previous.get(column, row) {
+                continue;
+            }
+
+            if cursor != Some((column, row)) {
+                out.extend_from_slice(format!("\x1b[{};{}H", row + 1, column + 1).as_bytes());
+            }
+            if glyph.style != style {
+                out.extend_from_slice(glyph.style.sgr().as_bytes());
+                style = glyph.style;
+            }
+
+            let mut buf = [0; 4];
+            out.extend_from_slice(glyph.ch.encode_utf8(&mut buf).as_bytes());
+            cursor = Some((column + 1, row));
+        }
+    }
+
+    if style != Style::default() {
+        out.extend_from_slice(Style::default().sgr().as_bytes());
+    }
+
+    out
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn first_frame_is_drawn_in_full() {
+        let mut frame = Frame::default();
+        frame.println("ab");
+        assert_eq!(diff(None, &frame), b"\x1b[0m\x1b[2J\x1b[1;1Hab".to_vec());
+    }
+
+    #[test]
+    fn only_changed_cells_are_sent() {
+        let mut previous = Frame::default();
+        previous.println("abcd");
+        previous.println("efgh");
+
+        let mut next = previous.clone();
+        let style = Style::fg(Color::Red);
+        next.put(2, 1, Glyph { ch: 'X', style });
+        next.put(3, 1, Glyph { ch: 'Y', style });
+
+        assert_eq!(
+            diff(Some(&previous), &next),
+            b"\x1b[2;3H\x1b[0;31mXY\x1b[0m".to_vec()
+        );
+        assert_eq!(diff(Some(&next), &next), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn shorter_frame_erases_leftovers() {
+        let mut previous = Frame::default();
+        previous.println("abc");
+        previous.println("def");
+
+        let mut next = Frame::default();
+        next.println("ab");
+
+        assert_eq!(
+            diff(Some(&previous), &next),
+            b"\x1b[1;3H \x1b[2;1H   ".to_vec()
+        );
+    }
+}
@@ -0,0 +1,71 @@
+/// Small xorshift64* generator. Good enough for placing ships and picking
+/// shots, and reproducible when created from a seed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+#[cfg(feature = "std")]
+impl Default for Rng {
+    /// Seeds the generator from the current time.
+    fn default() -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Rng::new(nanos ^ u64::from(std::process::id()))
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero, and similar seeds should not give
+        // similar sequences, so the seed is scrambled first
+        let state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ 0xD1B5_4A32_D192_ED03;
+        Rng {
+            state: if state == 0 { 1 } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        debug_assert!(bound > 0);
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn coin(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            assert!(rng.below(10) < 10);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+/// Variations of the game that can be picked for a match.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub name: &'static str,
+    /// Whether ships may be placed next to each other.
+    pub touching: bool,
+    /// Whether the water around a sunk ship is marked as shot, since no
+    /// ship can be there.
+    pub reveal_sunk: bool,
+    /// Whether a player fires one shot for every ship still afloat each
+    /// turn, instead of a single shot.
+    pub salvo: bool,
+}
+
+pub const RULES: [Rules; 3] = [Rules::CLASSIC, Rules::RELAXED, Rules::SALVO];
+
+impl Rules {
+    pub const CLASSIC: Rules = Rules {
+        name: "classic",
+        touching: false,
+        reveal_sunk: true,
+        salvo: false,
+    };
+
+    /// Ships may touch, and nothing is revealed around sunk ships.
+    pub const RELAXED: Rules = Rules {
+        name: "relaxed",
+        touching: true,
+        reveal_sunk: false,
+        salvo: false,
+    };
+
+    pub const SALVO: Rules = Rules {
+        name: "salvo",
+        salvo: true,
+        ..Rules::CLASSIC
+    };
+
+    pub fn by_name(name: &str) -> Option<Rules> {
+        RULES.into_iter().find(|rules| rules.name == name)
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::CLASSIC
+    }
+}
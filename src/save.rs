@@ -0,0 +1,106 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::codec;
+use crate::config::data_dir;
+use crate::game::{Game, Player, SHIPS_COUNT};
+
+pub const SAVE_FILE: &str = "save.bin";
+
+pub fn save_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(SAVE_FILE))
+}
+
+/// Writes the game and the player who shoots next to the save slot.
+pub fn save_game(game: &Game, turn: Player) -> io::Result<PathBuf> {
+    let path = save_path().ok_or_else(|| invalid("cannot find the home directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(&path, encode(game, turn)?)?;
+    Ok(path)
+}
+
+/// Saves a game in which the player is still placing the fleet, with the
+/// ships placed so far.
+pub fn save_placing(
+    game: &Game,
+    player: Player,
+    ships: &[u128; SHIPS_COUNT],
+    turn: Player,
+) -> io::Result<PathBuf> {
+    let mut game = *game;
+    game.place_partial_fleet(player, *ships).map_err(invalid)?;
+    save_game(&game, turn)
+}
+
+/// Reads the saved game, if there is one. The save slot is emptied, so the
+/// same game cannot be resumed twice.
+pub fn take_saved_game() -> io::Result<Option<(Game, Player)>> {
+    let saved = read_saved_game()?;
+    if let (Some(_), Some(path)) = (&saved, save_path()) {
+        fs::remove_file(path)?;
+    }
+    Ok(saved)
+}
+
+/// Reads the saved game and leaves it in the save slot.
+pub fn read_saved_game() -> io::Result<Option<(Game, Player)>> {
+    let Some(path) = save_path() else {
+        return Ok(None);
+    };
+
+    match fs::read(&path) {
+        Ok(bytes) => decode(&bytes).map(Some),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The save file contents, see [`codec`] for the format.
+pub fn encode(game: &Game, turn: Player) -> io::Result<Vec<u8>> {
+    codec::to_vec(game, turn).map_err(invalid)
+}
+
+pub fn decode(bytes: &[u8]) -> io::Result<(Game, Player)> {
+    let (game, turn, _) = codec::decode(bytes).map_err(invalid)?;
+    Ok((game, turn))
+}
+
+fn invalid(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::{board_api::create_ship, rng::Rng, rules::Rules};
+
+    #[test]
+    fn save_round_trip() {
+        let mut game = Game {
+            rules: Rules::RELAXED,
+            ..Game::default()
+        };
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(3));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(4));
+        game.shoot(Player::Alpha, create_ship(1)).unwrap();
+
+        let (loaded, turn) = decode(&encode(&game, Player::Beta).unwrap()).unwrap();
+        assert_eq!(turn, Player::Beta);
+        assert_eq!(loaded, game);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(decode(b"hello").is_err());
+        assert!(decode(b"").is_err());
+
+        let mut bytes = encode(&Game::default(), Player::Alpha).unwrap();
+        bytes[3] = 9;
+        assert!(decode(&bytes).is_err());
+    }
+}
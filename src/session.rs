@@ -0,0 +1,189 @@
+//! The turn loop every interface shares: fleets are placed, then players
+//! shoot in turns until somebody wins, concedes or quits. An interface
+//! only says how a player places ships and picks cells, and what it shows
+//! along the way.
+
+use crate::game::{Game, Interrupt, Player, Shot, PLAYERS};
+
+/// How an interface runs both players of a game. Every step may be
+/// interrupted: conceding ends the game, quitting leaves it unfinished.
+pub trait Frontend {
+    /// Places the fleet of a player who has none yet.
+    fn place_fleet(&mut self, game: &mut Game, player: Player) -> Result<(), Interrupt>;
+
+    /// Both fleets are placed and shooting begins.
+    fn start(&mut self, _game: &Game) {}
+
+    /// The player begins a turn with `shots` shots left, fewer when a
+    /// saved game stopped in the middle of it.
+    fn start_turn(&mut self, game: &Game, player: Player, shots: usize) -> Result<(), Interrupt>;
+
+    /// A cell the player did not shoot at and did not see revealed.
+    fn next_shot(&mut self, game: &Game, player: Player) -> Result<u128, Interrupt>;
+
+    /// The player fired at `shoot`, already marked in the game.
+    fn fired(
+        &mut self,
+        game: &Game,
+        player: Player,
+        shoot: u128,
+        shot: Shot,
+    ) -> Result<(), Interrupt>;
+}
+
+/// Plays the game from the given state until it is over. `first` starts
+/// unless a shot was fired already. Only quitting is returned as an error,
+/// a concession just ends the game.
+pub fn play(game: &mut Game, first: Player, frontend: &mut impl Frontend) -> Result<(), Interrupt> {
+    for player in PLAYERS {
+        if game.has_fleet(player) {
+            continue;
+        }
+        if let Err(interrupt) = frontend.place_fleet(game, player) {
+            return settle(game, player, interrupt);
+        }
+        debug_assert!(game.has_fleet(player), "the fleet was placed");
+    }
+
+    frontend.start(game);
+
+    let mut shooting = None;
+    while !game.is_over() {
+        let (player, shots) = game.turn.unwrap_or((first, game.shots_per_turn(first)));
+        if shooting != Some(player) {
+            shooting = Some(player);
+            if let Err(interrupt) = frontend.start_turn(game, player, shots) {
+                return settle(game, player, interrupt);
+            }
+        }
+
+        let shoot = match frontend.next_shot(game, player) {
+            Ok(shoot) => shoot,
+            Err(interrupt) => return settle(game, player, interrupt),
+        };
+        let shot = game
+            .shoot(player, shoot)
+            .expect("frontends fire at unknown cells");
+        if let Err(interrupt) = frontend.fired(game, player, shoot, shot) {
+            return settle(game, player, interrupt);
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies an interrupt of the player: a concession ends the game, quitting
+/// is passed on.
+pub fn settle(game: &mut Game, player: Player, interrupt: Interrupt) -> Result<(), Interrupt> {
+    match interrupt {
+        Interrupt::Concede => {
+            game.concede(player);
+            Ok(())
+        }
+        Interrupt::Quit => Err(Interrupt::Quit),
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::{ai::InOrderStrategy, ai::Strategy, rng::Rng, rules::Rules, view::PlayerView};
+
+    /// Two computers that stop with `interrupt` once `limit` shots were
+    /// fired, remembering the turns they were given.
+    #[allow(dead_code)]
+    struct Computers {
+        rng: Rng,
+        limit: usize,
+        interrupt: Interrupt,
+        turns: [(Player, usize); 8],
+        started: usize,
+    }
+
+    impl Frontend for Computers {
+        fn place_fleet(&mut self, game: &mut Game, player: Player) -> Result<(), Interrupt> {
+            game.place_random_fleet(player, &mut self.rng);
+            Ok(())
+        }
+
+        fn start_turn(
+            &mut self,
+            _game: &Game,
+            player: Player,
+            shots: usize,
+        ) -> Result<(), Interrupt> {
+            if let Some(turn) = self.turns.get_mut(self.started) {
+                *turn = (player, shots);
+            }
+            self.started += 1;
+            Ok(())
+        }
+
+        fn next_shot(&mut self, game: &Game, player: Player) -> Result<u128, Interrupt> {
+            match game.moves.len() == self.limit {
+                true => Err(self.interrupt),
+                false => {
+                    let view = PlayerView::new(game, player);
+                    Ok(InOrderStrategy.next_shot(&view, &mut self.rng))
+                }
+            }
+        }
+
+        fn fired(
+            &mut self,
+            _game: &Game,
+            _player: Player,
+            _shoot: u128,
+            _shot: Shot,
+        ) -> Result<(), Interrupt> {
+            Ok(())
+        }
+    }
+
+    #[allow(dead_code)]
+    fn computers(limit: usize, interrupt: Interrupt) -> Computers {
+        Computers {
+            rng: Rng::new(4),
+            limit,
+            interrupt,
+            turns: [(Player::Alpha, 0); 8],
+            started: 0,
+        }
+    }
+
+    #[test]
+    fn turns_follow_the_rules() {
+        let mut game = Game {
+            rules: Rules::SALVO,
+            ..Game::default()
+        };
+        let mut frontend = computers(usize::MAX, Interrupt::Quit);
+        assert_eq!(play(&mut game, Player::Beta, &mut frontend), Ok(()));
+        assert!(game.is_over());
+        assert_eq!(game.conceded, None);
+        assert_eq!(frontend.turns[0], (Player::Beta, 5));
+        assert_eq!(frontend.turns[1], (Player::Alpha, 5));
+        assert_eq!(frontend.turns[2].0, Player::Beta);
+    }
+
+    #[test]
+    fn resumed_turns_go_on() {
+        let mut game = Game {
+            rules: Rules::SALVO,
+            ..Game::default()
+        };
+        let mut frontend = computers(2, Interrupt::Quit);
+        assert_eq!(
+            play(&mut game, Player::Alpha, &mut frontend),
+            Err(Interrupt::Quit)
+        );
+        assert!(!game.is_over());
+
+        let mut frontend = computers(3, Interrupt::Concede);
+        assert_eq!(play(&mut game, Player::Beta, &mut frontend), Ok(()));
+        assert_eq!(frontend.turns[0], (Player::Alpha, 3));
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.conceded, Some(Player::Alpha));
+    }
+}
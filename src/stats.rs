@@ -0,0 +1,324 @@
+//! Statistics of every player across games, kept in [`STATS_FILE`] in the
+//! data directory. Players are known by name: the name given with
+//! `--name`, or the strategy of a computer player.
+//!
+//! The file has a line for each player, with the name and then the
+//! numbers of [`Profile`] in order:
+//!
+//! ```text
+//! # name games wins shots hits winning_shots longest_streak
+//! alice 5 3 210 61 118 6
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::ai::STRATEGY_NAMES;
+use crate::config::{data_dir, ConfigError};
+use crate::game::{Game, Player, Shot, PLAYERS};
+
+pub const STATS_FILE: &str = "stats.txt";
+
+const HEADER: &str = "# name games wins shots hits winning_shots longest_streak";
+
+/// What one player did in all the games they finished.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub games: u64,
+    pub wins: u64,
+    pub shots: u64,
+    pub hits: u64,
+    /// Shots fired in the games the player won.
+    pub winning_shots: u64,
+    /// Most hits in a row in one game.
+    pub longest_streak: u64,
+}
+
+impl Profile {
+    /// Share of the shots that hit, from 0 to 1.
+    pub fn accuracy(&self) -> Option<f64> {
+        match self.shots {
+            0 => None,
+            shots => Some(self.hits as f64 / shots as f64),
+        }
+    }
+
+    pub fn average_shots_to_win(&self) -> Option<f64> {
+        match self.wins {
+            0 => None,
+            wins => Some(self.winning_shots as f64 / wins as f64),
+        }
+    }
+
+    /// Adds a finished game, as played by the player.
+    pub fn add_game(&mut self, game: &Game, player: Player) {
+        let (mut shots, mut hits, mut streak) = (0, 0, 0);
+
+        for (mv, shot, _) in game.replay() {
+            if mv.player != player {
+                continue;
+            }
+            shots += 1;
+            match shot {
+                Shot::Miss => streak = 0,
+                Shot::Hit | Shot::Sunk(_) => {
+                    hits += 1;
+                    streak += 1;
+                    self.longest_streak = self.longest_streak.max(streak);
+                }
+            }
+        }
+
+        self.games += 1;
+        self.shots += shots;
+        self.hits += hits;
+        if game.get_winner() == Some(player) {
+            self.wins += 1;
+            self.winning_shots += shots;
+        }
+    }
+}
+
+/// One line about the player, for the end of a game.
+pub fn summary(name: &str, profile: &Profile) -> String {
+    let accuracy = profile.accuracy().map_or(String::from("-"), |accuracy| {
+        format!("{:.0}%", accuracy * 100.0)
+    });
+    let average = profile
+        .average_shots_to_win()
+        .map_or(String::from("-"), |average| format!("{:.1}", average));
+    format!(
+        "{}: {} of {} games won, {} accuracy, {} shots per win, {} hits in a row at best.",
+        name, profile.wins, profile.games, accuracy, average, profile.longest_streak
+    )
+}
+
+/// The profiles of all players, in the order they first played.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub profiles: Vec<(String, Profile)>,
+}
+
+impl Stats {
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, profile)| profile)
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        let i = match self.profiles.iter().position(|(other, _)| other == name) {
+            Some(i) => i,
+            None => {
+                self.profiles.push((String::from(name), Profile::default()));
+                self.profiles.len() - 1
+            }
+        };
+        &mut self.profiles[i].1
+    }
+
+    /// Adds a finished game to the players with a name. Players without
+    /// one, like somebody on the other end of the network, are skipped.
+    pub fn add_game(&mut self, game: &Game, names: [Option<&str>; 2]) {
+        for (player, name) in PLAYERS.into_iter().zip(names) {
+            if let Some(name) = name {
+                self.profile_mut(name).add_game(game, player);
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Stats, ConfigError> {
+        let mut stats = Stats::default();
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let name = words.next().expect("the line is not empty");
+            let numbers: Vec<u64> = words
+                .map(|word| word.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| ConfigError::syntax(line_number, "expected numbers after the name"))?;
+            let [games, wins, shots, hits, winning_shots, longest_streak] = numbers[..] else {
+                return Err(ConfigError::syntax(line_number, "expected 6 numbers"));
+            };
+            if stats.profile(name).is_some() {
+                return Err(ConfigError::syntax(line_number, "the player appears twice"));
+            }
+
+            stats.profiles.push((
+                String::from(name),
+                Profile {
+                    games,
+                    wins,
+                    shots,
+                    hits,
+                    winning_shots,
+                    longest_streak,
+                },
+            ));
+        }
+
+        Ok(stats)
+    }
+
+    /// Reads the statistics file. Without one nobody has played yet.
+    pub fn load() -> Result<Stats, ConfigError> {
+        let Some(path) = stats_path() else {
+            return Ok(Stats::default());
+        };
+
+        match fs::read_to_string(path) {
+            Ok(text) => Stats::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Stats::default()),
+            Err(err) => Err(ConfigError::Io(err)),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = stats_path().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "cannot find the home directory")
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+}
+
+/// The statistics file contents.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (name, profile) in &self.profiles {
+            writeln!(
+                f,
+                "{} {} {} {} {} {} {}",
+                name,
+                profile.games,
+                profile.wins,
+                profile.shots,
+                profile.hits,
+                profile.winning_shots,
+                profile.longest_streak
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn stats_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(STATS_FILE))
+}
+
+/// Longest name in bytes, short enough to send over the network.
+pub const MAX_NAME: usize = 32;
+
+/// Whether the name can be used for a profile: not empty and without
+/// spaces, since the file separates fields with them. Strategy names are
+/// kept for the computer players.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME
+        && !name.contains(char::is_whitespace)
+        && !name.starts_with('#')
+        && !STRATEGY_NAMES.contains(&name)
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::coord::Coord;
+
+    #[allow(dead_code)]
+    fn game_with_shots(shots: &[(Player, &str)]) -> Game {
+        let mut game = Game::default();
+        for player in PLAYERS {
+            for (layer, row) in (0..5).enumerate() {
+                let size = crate::game::SHIP_SIZES[layer];
+                let ship = crate::game::ship_at(Coord::new(0, row * 2), size, false).unwrap();
+                game.add_ship(player, ship, layer).unwrap();
+            }
+        }
+        for (player, cell) in shots {
+            game.shoot(*player, cell.parse::<Coord>().unwrap().to_board())
+                .unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn profiles_count_games() {
+        use Player::*;
+        let game = game_with_shots(&[
+            (Alpha, "A1"),
+            (Beta, "J10"),
+            (Alpha, "B1"),
+            (Beta, "A1"),
+            (Alpha, "J1"),
+            (Beta, "B1"),
+            (Alpha, "C1"),
+        ]);
+
+        let mut profile = Profile::default();
+        profile.add_game(&game, Alpha);
+        assert_eq!((profile.games, profile.wins), (1, 0));
+        assert_eq!((profile.shots, profile.hits), (4, 3));
+        assert_eq!(profile.longest_streak, 2);
+        assert_eq!(profile.accuracy(), Some(0.75));
+        assert_eq!(profile.average_shots_to_win(), None);
+
+        let mut conceded = game;
+        conceded.concede(Beta);
+        profile.add_game(&conceded, Alpha);
+        assert_eq!(
+            (profile.games, profile.wins, profile.winning_shots),
+            (2, 1, 4)
+        );
+        assert_eq!(profile.average_shots_to_win(), Some(4.0));
+        assert!(summary("alice", &profile).starts_with("alice: 1 of 2 games won, 75% accuracy"));
+    }
+
+    #[test]
+    fn stats_round_trip() {
+        let mut game = game_with_shots(&[(Player::Alpha, "A1")]);
+        game.concede(Player::Beta);
+
+        let mut stats = Stats::default();
+        stats.add_game(&game, [Some("alice"), Some("hunt")]);
+        stats.add_game(&game, [Some("bob"), None]);
+        stats.add_game(&game, [Some("alice"), Some("hunt")]);
+        assert_eq!(stats.profiles.len(), 3);
+        assert_eq!(stats.profile("alice").unwrap().wins, 2);
+        assert_eq!(stats.profile("hunt").unwrap().games, 2);
+
+        let text = stats.to_string();
+        assert!(text.starts_with("# name"));
+        assert_eq!(Stats::parse(&text).unwrap(), stats);
+    }
+
+    #[test]
+    fn rejects_bad_stats() {
+        for text in [
+            "alice 1 2 3",
+            "alice 1 2 3 4 5 x",
+            "a 1 1 1 1 1 1\na 1 1 1 1 1 1",
+        ] {
+            assert!(Stats::parse(text).is_err(), "{}", text);
+        }
+        assert!(is_valid_name("alice"));
+        assert!(!is_valid_name("alice smith"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name(&"a".repeat(MAX_NAME + 1)));
+        assert!(!is_valid_name("hunt"));
+        assert!(is_valid_name("hunter"));
+    }
+}
@@ -0,0 +1,386 @@
+use std::io;
+use std::panic;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use termios::{tcsetattr, Termios, ECHO, ICANON, ISIG, TCSANOW, VMIN, VTIME};
+
+/// How long to wait for the rest of an escape sequence before deciding
+/// that a lone Escape was pressed.
+const ESCAPE_TIMEOUT_MS: i32 = 30;
+
+/// A decoded key press or mouse click.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    CtrlC,
+    /// The terminal window changed its size.
+    Resize,
+    /// Left mouse button press, in zero based terminal cells.
+    Click {
+        column: usize,
+        row: usize,
+    },
+}
+
+/// Result of decoding the start of an input byte stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Decoded {
+    /// A key and the number of bytes it took.
+    Key(Key, usize),
+    /// A complete sequence we do not care about, e.g. a mouse release.
+    Skip(usize),
+    /// The bytes are a prefix of a longer sequence.
+    Incomplete,
+}
+
+pub fn decode(bytes: &[u8]) -> Decoded {
+    let Some(&first) = bytes.first() else {
+        return Decoded::Incomplete;
+    };
+
+    match first {
+        0x1b => decode_escape(bytes),
+        0x03 => Decoded::Key(Key::CtrlC, 1),
+        b'\r' | b'\n' => Decoded::Key(Key::Enter, 1),
+        b'\t' => Decoded::Key(Key::Tab, 1),
+        0x7f | 0x08 => Decoded::Key(Key::Backspace, 1),
+        _ => decode_utf8(bytes),
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Decoded {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Decoded::Skip(1),
+    };
+
+    if bytes.len() < len {
+        return Decoded::Incomplete;
+    }
+
+    match std::str::from_utf8(&bytes[..len]) {
+        Ok(text) => Decoded::Key(Key::Char(text.chars().next().unwrap()), len),
+        Err(_) => Decoded::Skip(1),
+    }
+}
+
+fn decode_escape(bytes: &[u8]) -> Decoded {
+    match bytes.get(1) {
+        None => Decoded::Incomplete,
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => match bytes.get(2) {
+            None => Decoded::Incomplete,
+            Some(&last) => match cursor_key(last) {
+                Some(key) => Decoded::Key(key, 3),
+                None => Decoded::Skip(3),
+            },
+        },
+        // Escape followed by an ordinary key, e.g. Alt+key. Report the
+        // Escape alone and let the key be decoded on its own.
+        Some(_) => Decoded::Key(Key::Esc, 1),
+    }
+}
+
+/// Decodes `ESC [ params final` control sequences.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    // Legacy X10 mouse report: ESC [ M button column row
+    if bytes.get(2) == Some(&b'M') {
+        if bytes.len() < 6 {
+            return Decoded::Incomplete;
+        }
+        let button = bytes[3].wrapping_sub(32);
+        let column = usize::from(bytes[4].wrapping_sub(33));
+        let row = usize::from(bytes[5].wrapping_sub(33));
+
+        return match button & 0b11 {
+            0 => Decoded::Key(Key::Click { column, row }, 6),
+            _ => Decoded::Skip(6),
+        };
+    }
+
+    let Some(end) = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+        return Decoded::Incomplete;
+    };
+    let end = end + 2;
+    let len = end + 1;
+    let params = &bytes[2..end];
+
+    let key = match (params, bytes[end]) {
+        (b"", last) => cursor_key(last),
+        (b"1" | b"7", b'~') => Some(Key::Home),
+        (b"4" | b"8", b'~') => Some(Key::End),
+        ([b'<', rest @ ..], b'M') => return decode_sgr_mouse(rest, len),
+        _ => None,
+    };
+
+    match key {
+        Some(key) => Decoded::Key(key, len),
+        None => Decoded::Skip(len),
+    }
+}
+
+/// Decodes the parameters of an SGR mouse press, `ESC [ < b ; x ; y M`.
+fn decode_sgr_mouse(params: &[u8], len: usize) -> Decoded {
+    let mut numbers = params
+        .split(|&b| b == b';')
+        .map(|part| std::str::from_utf8(part).ok()?.parse::<usize>().ok());
+
+    let (Some(Some(button)), Some(Some(column)), Some(Some(row))) =
+        (numbers.next(), numbers.next(), numbers.next())
+    else {
+        return Decoded::Skip(len);
+    };
+
+    // Only plain left button presses, no drags or wheel
+    if button != 0 || column == 0 || row == 0 {
+        return Decoded::Skip(len);
+    }
+
+    Decoded::Key(
+        Key::Click {
+            column: column - 1,
+            row: row - 1,
+        },
+        len,
+    )
+}
+
+fn cursor_key(last: u8) -> Option<Key> {
+    match last {
+        b'A' => Some(Key::Up),
+        b'B' => Some(Key::Down),
+        b'C' => Some(Key::Right),
+        b'D' => Some(Key::Left),
+        b'H' => Some(Key::Home),
+        b'F' => Some(Key::End),
+        _ => None,
+    }
+}
+
+/// Bytes read after the last decoded key, like a key pressed right after
+/// Escape that arrived in the same read. The next `read_key` starts here.
+static PENDING: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Blocks until a whole key press is read from stdin. Expects the terminal
+/// to be in raw mode, see [`RawMode`].
+pub fn read_key() -> Key {
+    let mut bytes = PENDING.lock().unwrap_or_else(|err| err.into_inner());
+
+    loop {
+        loop {
+            match decode(&bytes) {
+                Decoded::Key(key, len) => {
+                    bytes.drain(..len);
+                    return key;
+                }
+                Decoded::Skip(len) => {
+                    bytes.drain(..len);
+                }
+                Decoded::Incomplete if bytes.first() == Some(&0x1b) && !input_pending() => {
+                    bytes.drain(..1);
+                    return Key::Esc;
+                }
+                Decoded::Incomplete => break,
+            }
+        }
+
+        if bytes.is_empty() && RESIZED.swap(false, Ordering::Relaxed) {
+            return Key::Resize;
+        }
+
+        let mut buf = [0u8; 1];
+        match read_byte(&mut buf) {
+            Ok(()) => bytes.push(buf[0]),
+            // A signal, most likely SIGWINCH, checked above
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => panic!("cannot read the keyboard: {}", err),
+        }
+    }
+}
+
+/// Reads straight from the file descriptor. The buffered `io::stdin` would
+/// swallow the rest of an escape sequence where `poll` cannot see it.
+fn read_byte(buf: &mut [u8; 1]) -> io::Result<()> {
+    match unsafe { libc::read(0, buf.as_mut_ptr().cast(), 1) } {
+        1 => Ok(()),
+        0 => Err(io::ErrorKind::UnexpectedEof.into()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Columns and rows of the terminal, if stdout is one.
+pub fn terminal_size() -> Option<(usize, usize)> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(1, libc::TIOCGWINSZ, &mut size) } != 0 || size.ws_col == 0 {
+        return None;
+    }
+
+    Some((usize::from(size.ws_col), usize::from(size.ws_row)))
+}
+
+/// Set by the SIGWINCH handler, reported by `read_key` as [`Key::Resize`].
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_resize(_signal: libc::c_int) {
+    RESIZED.store(true, Ordering::Relaxed);
+}
+
+/// Checks whether more input arrives within the escape timeout.
+fn input_pending() -> bool {
+    let mut fd = libc::pollfd {
+        fd: 0,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    unsafe { libc::poll(&mut fd, 1, ESCAPE_TIMEOUT_MS) > 0 }
+}
+
+/// Switches to the alternate screen, hides the cursor and asks the terminal
+/// to report mouse clicks, in both X10 and SGR encoding.
+const SCREEN_ON: &[u8] = b"\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h";
+const SCREEN_OFF: &[u8] = b"\x1b[?1006l\x1b[?1000l\x1b[0m\x1b[?25h\x1b[?1049l";
+
+/// Terminal settings from before the game started. Kept in a static, so the
+/// panic hook and signal handlers can restore them too.
+static ORIGINAL: OnceLock<Termios> = OnceLock::new();
+
+/// Keeps the terminal in raw mode on the alternate screen, with mouse
+/// reporting, for as long as it is alive. The original settings are
+/// restored on drop, on panic and when the process is killed by SIGINT,
+/// SIGTERM or SIGHUP.
+pub struct RawMode {
+    _private: (),
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        let original = Termios::from_fd(0)?;
+        let original = *ORIGINAL.get_or_init(|| original);
+
+        let mut raw = original;
+        raw.c_lflag &= !(ECHO | ICANON | ISIG);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        tcsetattr(0, TCSANOW, &raw)?;
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+            let handler = on_signal as extern "C" fn(libc::c_int);
+            unsafe { libc::signal(signal, handler as libc::sighandler_t) };
+        }
+
+        // Without SA_RESTART, so a blocked read returns and the screen can
+        // be drawn again for the new size
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_resize as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGWINCH, &action, ptr::null_mut());
+        }
+
+        write_raw(SCREEN_ON);
+
+        Ok(RawMode { _private: () })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Puts the terminal back the way it was. Only uses async-signal-safe calls,
+/// so it can run inside a signal handler.
+fn restore() {
+    write_raw(SCREEN_OFF);
+    if let Some(original) = ORIGINAL.get() {
+        let _ = tcsetattr(0, TCSANOW, original);
+    }
+}
+
+fn write_raw(bytes: &[u8]) {
+    unsafe { libc::write(1, bytes.as_ptr().cast(), bytes.len()) };
+}
+
+extern "C" fn on_signal(signal: libc::c_int) {
+    restore();
+
+    // Die from the same signal, so the parent sees the real exit reason
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Handles Ctrl-C read in raw mode the same way the terminal would.
+pub fn interrupt() -> ! {
+    unsafe { libc::raise(libc::SIGINT) };
+    unreachable!("SIGINT handler returned");
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn plain_keys() {
+        assert_eq!(decode(b"k"), Decoded::Key(Key::Char('k'), 1));
+        assert_eq!(decode(b"\n"), Decoded::Key(Key::Enter, 1));
+        assert_eq!(decode(b"\r"), Decoded::Key(Key::Enter, 1));
+        assert_eq!(decode(b"\x03"), Decoded::Key(Key::CtrlC, 1));
+        assert_eq!(decode("ж".as_bytes()), Decoded::Key(Key::Char('ж'), 2));
+        assert_eq!(decode(&"ж".as_bytes()[..1]), Decoded::Incomplete);
+    }
+
+    #[test]
+    fn arrow_keys() {
+        assert_eq!(decode(b"\x1b"), Decoded::Incomplete);
+        assert_eq!(decode(b"\x1b["), Decoded::Incomplete);
+        assert_eq!(decode(b"\x1b[A"), Decoded::Key(Key::Up, 3));
+        assert_eq!(decode(b"\x1b[D"), Decoded::Key(Key::Left, 3));
+        assert_eq!(decode(b"\x1bOC"), Decoded::Key(Key::Right, 3));
+        assert_eq!(decode(b"\x1b[H"), Decoded::Key(Key::Home, 3));
+        assert_eq!(decode(b"\x1b[4~"), Decoded::Key(Key::End, 4));
+        assert_eq!(decode(b"\x1b[1~"), Decoded::Key(Key::Home, 4));
+        assert_eq!(decode(b"\x1b[15~"), Decoded::Skip(5));
+        assert_eq!(decode(b"\x1bq"), Decoded::Key(Key::Esc, 1));
+    }
+
+    #[test]
+    fn mouse_clicks() {
+        assert_eq!(
+            decode(b"\x1b[<0;12;3M"),
+            Decoded::Key(Key::Click { column: 11, row: 2 }, 10)
+        );
+        assert_eq!(decode(b"\x1b[<0;12;3m"), Decoded::Skip(10));
+        assert_eq!(decode(b"\x1b[<64;12;3M"), Decoded::Skip(11));
+        assert_eq!(decode(b"\x1b[<0;12"), Decoded::Incomplete);
+        assert_eq!(
+            decode(&[0x1b, b'[', b'M', 32, 33 + 4, 33 + 7]),
+            Decoded::Key(Key::Click { column: 4, row: 7 }, 6)
+        );
+        assert_eq!(decode(&[0x1b, b'[', b'M', 35, 40, 40]), Decoded::Skip(6));
+    }
+}
@@ -0,0 +1,390 @@
+//! Boards and games as plain text grids, for tests and bug reports. A row
+//! is ten cells, with or without spaces between them:
+//!
+//! ```text
+//! .  water
+//! #  ship
+//! x  ship that was hit
+//! o  water that was shot
+//! ```
+//!
+//! A bare bitboard only uses `.` and `#`, and rows left out at the bottom
+//! are water. The [`board!`](crate::board) macro reads one at compile time.
+//!
+//! A game lists its rules, who conceded if anybody did, and then each
+//! player's board with the enemy shots on it:
+//!
+//! ```text
+//! rules classic
+//! alpha
+//! # # # # # . . . . .
+//! . . . . . . . . . .
+//! x x x x . . . . o .
+//! beta
+//! . o . . . . . . . .
+//! ```
+//!
+//! Ships are told apart by the gaps between them, so ships that touch, as
+//! the relaxed rules allow, may not come back the same. The order of the
+//! moves is not kept.
+
+use core::fmt;
+
+use crate::board_api::board_get;
+use crate::constants::BOARD_SIZE;
+use crate::coord::Coord;
+use crate::game::{Game, Player, PLAYERS, SHIPS_COUNT, SHIP_SIZES};
+use crate::rules::Rules;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextError {
+    /// Line of the text, starting at 1.
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl core::error::Error for TextError {}
+
+/// A bitboard from a text grid, checked when compiling:
+///
+/// ```
+/// use battleship::{board, coord::Coord};
+///
+/// let ship = board!("
+///     . . . . . . . . . .
+///     . . . . # # . . . .
+/// ");
+/// assert_eq!(ship, Coord::new(4, 1).to_board() | Coord::new(5, 1).to_board());
+/// ```
+#[macro_export]
+macro_rules! board {
+    ($text:expr) => {
+        const {
+            match $crate::text::parse_board($text) {
+                Ok(board) => board,
+                Err(err) => panic!("{}", err.message),
+            }
+        }
+    };
+}
+
+/// Reads a bitboard of `.` and `#` cells. Blank lines are skipped.
+pub const fn parse_board(text: &str) -> Result<u128, TextError> {
+    let bytes = text.as_bytes();
+    let mut board = 0;
+    let mut y = 0;
+    let mut line = 1;
+    let mut start = 0;
+
+    while start <= bytes.len() {
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != b'\n' {
+            end += 1;
+        }
+        let (row, _) = bytes.split_at(end);
+        let (_, row) = row.split_at(start);
+
+        match parse_row(row) {
+            Ok(None) => {}
+            Ok(Some(_)) if y == BOARD_SIZE => return Err(error(line, "more than 10 rows")),
+            Ok(Some(cells)) => {
+                let mut x = 0;
+                while x < BOARD_SIZE {
+                    match cells[x] {
+                        b'.' => {}
+                        b'#' => board |= Coord::new(x, y).to_board(),
+                        _ => return Err(error(line, "a board only has `.` and `#` cells")),
+                    }
+                    x += 1;
+                }
+                y += 1;
+            }
+            Err(message) => return Err(error(line, message)),
+        }
+
+        start = end + 1;
+        line += 1;
+    }
+
+    Ok(board)
+}
+
+/// The cells of a row, or `None` for a blank line.
+const fn parse_row(row: &[u8]) -> Result<Option<[u8; BOARD_SIZE]>, &'static str> {
+    let mut cells = [0; BOARD_SIZE];
+    let mut count = 0;
+    let mut i = 0;
+
+    while i < row.len() {
+        match row[i] {
+            b' ' | b'\t' | b'\r' => {}
+            cell @ (b'.' | b'#' | b'x' | b'o') => {
+                if count == BOARD_SIZE {
+                    return Err("a row has 10 cells");
+                }
+                cells[count] = cell;
+                count += 1;
+            }
+            _ => return Err("unknown cell, expected `.`, `#`, `x` or `o`"),
+        }
+        i += 1;
+    }
+
+    match count {
+        0 => Ok(None),
+        BOARD_SIZE => Ok(Some(cells)),
+        _ => Err("a row has 10 cells"),
+    }
+}
+
+const fn error(line: usize, message: &'static str) -> TextError {
+    TextError { line, message }
+}
+
+/// Writes a bitboard as `.` and `#` rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BoardText(pub u128);
+
+impl fmt::Display for BoardText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_grid(f, |x, y| match board_get(self.0, x, y) {
+            true => '#',
+            false => '.',
+        })
+    }
+}
+
+/// Writes a game in the format read by [`parse_game`].
+#[derive(Copy, Clone, Debug)]
+pub struct GameText<'a>(pub &'a Game);
+
+impl fmt::Display for GameText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let game = self.0;
+        writeln!(f, "rules {}", game.rules.name)?;
+        if let Some(player) = game.conceded {
+            writeln!(f, "conceded {}", player.name())?;
+        }
+
+        for player in PLAYERS {
+            let ships = game.get_board(player);
+            let shots = game.get_shoots(player.other());
+            writeln!(f, "{}", player.name())?;
+            write_grid(f, |x, y| {
+                match (board_get(ships, x, y), board_get(shots, x, y)) {
+                    (false, false) => '.',
+                    (true, false) => '#',
+                    (true, true) => 'x',
+                    (false, true) => 'o',
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn write_grid(f: &mut fmt::Formatter<'_>, cell: impl Fn(usize, usize) -> char) -> fmt::Result {
+    for y in 0..BOARD_SIZE {
+        for x in 0..BOARD_SIZE {
+            let separator = if x + 1 < BOARD_SIZE { " " } else { "\n" };
+            write!(f, "{}{}", cell(x, y), separator)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a game written by [`GameText`]. Ships are checked against the
+/// rules, and the game has no moves, only the shots.
+pub fn parse_game(text: &str) -> Result<Game, TextError> {
+    let mut game = Game::default();
+    let mut grids = [Grid::default(); 2];
+    let mut player = None;
+
+    for (i, row) in text.lines().enumerate() {
+        let line = i + 1;
+        let words = row.trim();
+
+        if let Some(name) = words.strip_prefix("rules ") {
+            game.rules = Rules::by_name(name.trim()).ok_or(error(line, "unknown rules"))?;
+        } else if let Some(name) = words.strip_prefix("conceded ") {
+            game.conceded = Some(
+                name.trim()
+                    .parse::<Player>()
+                    .map_err(|_| error(line, "unknown player"))?,
+            );
+        } else if let Ok(named) = words.parse::<Player>() {
+            player = Some(named);
+        } else if let Some(cells) = parse_row(row.as_bytes()).map_err(|err| error(line, err))? {
+            let player = player.ok_or(error(line, "a board starts with `alpha` or `beta`"))?;
+            let grid = &mut grids[player.index()];
+            if grid.rows == BOARD_SIZE {
+                return Err(error(line, "more than 10 rows"));
+            }
+            for (x, cell) in cells.into_iter().enumerate() {
+                let board = Coord::new(x, grid.rows).to_board();
+                if let b'#' | b'x' = cell {
+                    grid.ships |= board;
+                }
+                if let b'x' | b'o' = cell {
+                    grid.shots |= board;
+                }
+            }
+            grid.rows += 1;
+            grid.last_line = line;
+        }
+    }
+
+    for player in PLAYERS {
+        let grid = grids[player.index()];
+        let line = grid.last_line;
+        for (layer, ship) in split_ships(grid.ships).map_err(|err| error(line, err))? {
+            if ship != 0 && game.add_ship(player, ship, layer).is_err() {
+                return Err(error(line, "ships are too close by the rules"));
+            }
+        }
+    }
+    game.shoots_beta = grids[0].shots;
+    game.shoots_alpha = grids[1].shots;
+
+    Ok(game)
+}
+
+#[derive(Copy, Clone, Default)]
+struct Grid {
+    ships: u128,
+    shots: u128,
+    rows: usize,
+    /// For errors about the ships, which are only known at the end.
+    last_line: usize,
+}
+
+/// Finds the ships on a board, in reading order, and the layer of each.
+/// Ships of the same size take the layers in order.
+fn split_ships(mut board: u128) -> Result<[(usize, u128); SHIPS_COUNT], &'static str> {
+    let mut ships = [(0, 0); SHIPS_COUNT];
+    let mut taken = [false; SHIPS_COUNT];
+    let mut count = 0;
+
+    while let Some(start) = Coord::from_board(board) {
+        let across = start.x + 1 < BOARD_SIZE && board_get(board, start.x + 1, start.y);
+        let mut ship = 0;
+        let mut cell = start;
+        while cell.x < BOARD_SIZE && cell.y < BOARD_SIZE && board_get(board, cell.x, cell.y) {
+            ship |= cell.to_board();
+            cell = match across {
+                true => Coord::new(cell.x + 1, cell.y),
+                false => Coord::new(cell.x, cell.y + 1),
+            };
+        }
+        board &= !ship;
+
+        let size = ship.count_ones() as usize;
+        let layer = (0..SHIPS_COUNT)
+            .find(|&layer| SHIP_SIZES[layer] == size && !taken[layer])
+            .ok_or("a ship has no size left in the fleet")?;
+        taken[layer] = true;
+        ships[count] = (layer, ship);
+        count += 1;
+    }
+
+    match count {
+        // An empty board has no fleet yet
+        0 => Ok([(0, 0); SHIPS_COUNT]),
+        SHIPS_COUNT => Ok(ships),
+        _ => Err("the fleet is not complete"),
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[cfg(test)]
+    use crate::ai::played;
+
+    #[test]
+    fn boards_round_trip() {
+        let board = board!(
+            "
+            # . . . . . . . . .
+            . . . . . . . . . .
+            . . # # # . . . . .
+            "
+        );
+        assert_eq!(
+            board,
+            Coord::new(0, 0).to_board()
+                | Coord::new(2, 2).to_board()
+                | Coord::new(3, 2).to_board()
+                | Coord::new(4, 2).to_board()
+        );
+
+        let text = BoardText(board).to_string();
+        assert!(text.starts_with("# . . . . . . . . .\n. . . "));
+        assert_eq!(parse_board(&text), Ok(board));
+        assert_eq!(
+            parse_board("#.........\n.........#"),
+            Ok(Coord::new(0, 0).to_board() | Coord::new(9, 1).to_board())
+        );
+    }
+
+    #[test]
+    fn rejects_bad_boards() {
+        assert_eq!(
+            parse_board("# # #").unwrap_err().message,
+            "a row has 10 cells"
+        );
+        assert_eq!(parse_board("\n. . . . . . . . . . x").unwrap_err().line, 2);
+        assert!(parse_board(". . . . . . . . . x").is_err());
+        assert!(parse_board(&".........\n".repeat(11)).is_err());
+        assert!(parse_board(&"..........\n".repeat(11)).is_err());
+    }
+
+    #[test]
+    fn games_round_trip() {
+        let mut game = played(Rules::CLASSIC, 8, 60);
+        game.concede(Player::Beta);
+
+        let text = GameText(&game).to_string();
+        let parsed = parse_game(&text).unwrap();
+        assert_eq!(parsed.ships_alpha, game.ships_alpha);
+        assert_eq!(parsed.ships_beta, game.ships_beta);
+        assert_eq!(parsed.shoots_alpha, game.shoots_alpha);
+        assert_eq!(parsed.shoots_beta, game.shoots_beta);
+        assert_eq!(parsed.conceded, Some(Player::Beta));
+        assert_eq!(GameText(&parsed).to_string(), text);
+    }
+
+    #[test]
+    fn reads_a_game() {
+        let game = parse_game(
+            "
+            rules relaxed
+            alpha
+            # # # # # . . . . .
+            # # # # . . . . . .
+            x x x o . . . . . .
+            # # # . . . . . . .
+            # # . . . . . . . .
+            beta
+            . . . . . . . . . #
+            . . . . . . . . . #
+            ",
+        );
+        let Err(err) = game else {
+            panic!("beta has two ships");
+        };
+        assert_eq!((err.line, err.message), (11, "the fleet is not complete"));
+
+        let game = parse_game("alpha\n. o . . . . . . . .").unwrap();
+        assert!(!game.has_fleet(Player::Alpha));
+        assert_eq!(game.shoots_beta, Coord::new(1, 0).to_board());
+        assert!(parse_game(". . . . . . . . . .").is_err());
+        assert!(parse_game("rules chess").is_err());
+    }
+}
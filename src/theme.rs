@@ -0,0 +1,615 @@
+use std::env;
+use std::fmt::Write;
+
+use crate::config::{parse_entries, read_config, ConfigError};
+use crate::render::Glyph;
+
+pub const THEME_FILE: &str = "theme.conf";
+
+/// Terminal columns taken by one board cell.
+pub const CELL_WIDTH: usize = 3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Color {
+    #[default]
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// One of the 256 indexed terminal colors.
+    Indexed(u8),
+}
+
+const COLOR_NAMES: [(&str, Color); 9] = [
+    ("default", Color::Default),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bold: bool,
+}
+
+impl Style {
+    pub const fn fg(fg: Color) -> Style {
+        Style { fg, bold: false }
+    }
+
+    pub const fn bold(self) -> Style {
+        Style { bold: true, ..self }
+    }
+
+    /// SGR escape sequence that switches the terminal to this style.
+    pub fn sgr(&self) -> String {
+        let mut sgr = String::from("\u{001B}[0");
+        if self.bold {
+            sgr.push_str(";1");
+        }
+        match self.fg {
+            Color::Default => {}
+            Color::Indexed(index) => write!(sgr, ";38;5;{}", index).unwrap(),
+            fg => {
+                let index = COLOR_NAMES
+                    .iter()
+                    .position(|&(_, color)| color == fg)
+                    .unwrap();
+                write!(sgr, ";{}", 29 + index).unwrap();
+            }
+        }
+        sgr.push('m');
+        sgr
+    }
+}
+
+/// Which colors the cells are drawn in. The colorblind palette replaces the
+/// theme colors with the Okabe-Ito scheme, which stays distinguishable with
+/// the common kinds of color blindness.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    #[default]
+    Standard,
+    Colorblind,
+}
+
+pub const PALETTES: [Palette; 2] = [Palette::Standard, Palette::Colorblind];
+
+impl Palette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "standard",
+            Palette::Colorblind => "colorblind",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Palette> {
+        PALETTES.into_iter().find(|palette| palette.name() == name)
+    }
+
+    /// Color of a cell kind, or `None` to keep the theme color.
+    fn color(&self, kind: CellKind) -> Option<Color> {
+        // Okabe-Ito colors, as the closest of the 256 indexed colors
+        const BLUE: Color = Color::Indexed(25);
+        const SKY_BLUE: Color = Color::Indexed(74);
+        const BLUISH_GREEN: Color = Color::Indexed(36);
+        const ORANGE: Color = Color::Indexed(214);
+        const VERMILLION: Color = Color::Indexed(166);
+        const YELLOW: Color = Color::Indexed(227);
+        const REDDISH_PURPLE: Color = Color::Indexed(175);
+
+        match self {
+            Palette::Standard => None,
+            Palette::Colorblind => Some(match kind {
+                CellKind::Unknown => BLUE,
+                CellKind::Miss => Color::Default,
+                CellKind::Hit => VERMILLION,
+                CellKind::Ship => SKY_BLUE,
+                CellKind::NewShip => BLUISH_GREEN,
+                CellKind::Collision => ORANGE,
+                CellKind::Crosshair => YELLOW,
+                CellKind::Incoming => REDDISH_PURPLE,
+            }),
+        }
+    }
+}
+
+/// What a board cell shows, independent of how it is drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CellKind {
+    Miss,
+    Hit,
+    #[default]
+    Unknown,
+    Ship,
+    Collision,
+    NewShip,
+    Crosshair,
+    /// Where the enemy fired last.
+    Incoming,
+}
+
+pub const CELL_KINDS: [CellKind; 8] = [
+    CellKind::Unknown,
+    CellKind::Miss,
+    CellKind::Hit,
+    CellKind::Ship,
+    CellKind::NewShip,
+    CellKind::Collision,
+    CellKind::Crosshair,
+    CellKind::Incoming,
+];
+
+impl CellKind {
+    /// Name of the cell in the theme file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellKind::Miss => "miss",
+            CellKind::Hit => "hit",
+            CellKind::Unknown => "unknown",
+            CellKind::Ship => "ship",
+            CellKind::Collision => "collision",
+            CellKind::NewShip => "new-ship",
+            CellKind::Crosshair => "crosshair",
+            CellKind::Incoming => "incoming",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CellKind::Miss => "miss",
+            CellKind::Hit => "hit",
+            CellKind::Unknown => "unknown water",
+            CellKind::Ship => "your ship",
+            CellKind::Collision => "ship too close to another",
+            CellKind::NewShip => "ship being placed",
+            CellKind::Crosshair => "crosshair",
+            CellKind::Incoming => "enemy's last shot",
+        }
+    }
+
+    fn index(&self) -> usize {
+        CELL_KINDS.iter().position(|kind| kind == self).unwrap()
+    }
+}
+
+/// How one kind of cell is drawn: a few characters in one style.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StyledCell {
+    pub text: [char; CELL_WIDTH],
+    pub style: Style,
+}
+
+impl StyledCell {
+    pub const fn new(text: [char; CELL_WIDTH], style: Style) -> StyledCell {
+        StyledCell { text, style }
+    }
+
+    pub fn glyphs(&self) -> [Glyph; CELL_WIDTH] {
+        self.text.map(|ch| Glyph {
+            ch,
+            style: self.style,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    cells: [StyledCell; CELL_KINDS.len()],
+    /// Left and right character around a highlighted cell.
+    highlight: [char; 2],
+    pub palette: Palette,
+}
+
+pub const THEMES: [fn() -> Theme; 5] = [
+    Theme::classic,
+    Theme::high_contrast,
+    Theme::colorblind,
+    Theme::unicode,
+    Theme::ascii,
+];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
+
+impl Theme {
+    /// Cells are given in the order of `CELL_KINDS`.
+    const fn new(
+        name: &'static str,
+        cells: [StyledCell; CELL_KINDS.len()],
+        highlight: [char; 2],
+    ) -> Theme {
+        Theme {
+            name,
+            cells,
+            highlight,
+            palette: Palette::Standard,
+        }
+    }
+
+    pub fn classic() -> Theme {
+        use Color::*;
+        Theme::new(
+            "classic",
+            [
+                StyledCell::new(['[', '~', ']'], Style::fg(Blue)),
+                StyledCell::new([' ', ' ', ' '], Style::fg(Default)),
+                StyledCell::new(['[', '*', ']'], Style::fg(Red)),
+                StyledCell::new(['[', 'O', ']'], Style::fg(Green)),
+                StyledCell::new(['[', 'n', ']'], Style::fg(Green)),
+                StyledCell::new(['[', 'X', ']'], Style::fg(Yellow)),
+                StyledCell::new(['{', '+', '}'], Style::fg(Yellow)),
+                StyledCell::new(['>', '!', '<'], Style::fg(Magenta).bold()),
+            ],
+            ['(', ')'],
+        )
+    }
+
+    pub fn high_contrast() -> Theme {
+        use Color::*;
+        Theme::new(
+            "high-contrast",
+            [
+                StyledCell::new(['[', '~', ']'], Style::fg(Indexed(12))),
+                StyledCell::new([' ', ' ', ' '], Style::fg(Default)),
+                StyledCell::new(['[', '*', ']'], Style::fg(Indexed(9)).bold()),
+                StyledCell::new(['[', '#', ']'], Style::fg(Indexed(15)).bold()),
+                StyledCell::new(['[', 'n', ']'], Style::fg(Indexed(10)).bold()),
+                StyledCell::new(['[', 'X', ']'], Style::fg(Indexed(11)).bold()),
+                StyledCell::new(['{', '+', '}'], Style::fg(Indexed(13)).bold()),
+                StyledCell::new(['>', '!', '<'], Style::fg(Indexed(14)).bold()),
+            ],
+            ['(', ')'],
+        )
+    }
+
+    /// The classic cells in the colors of the colorblind palette.
+    pub fn colorblind() -> Theme {
+        Theme {
+            name: "colorblind",
+            palette: Palette::Colorblind,
+            ..Theme::classic()
+        }
+    }
+
+    pub fn unicode() -> Theme {
+        use Color::*;
+        Theme::new(
+            "unicode",
+            [
+                StyledCell::new([' ', '·', ' '], Style::fg(Blue)),
+                StyledCell::new([' ', ' ', ' '], Style::fg(Default)),
+                StyledCell::new([' ', '✕', ' '], Style::fg(Red).bold()),
+                StyledCell::new([' ', '◼', ' '], Style::fg(Green)),
+                StyledCell::new([' ', '◻', ' '], Style::fg(Green)),
+                StyledCell::new([' ', '▣', ' '], Style::fg(Yellow)),
+                StyledCell::new(['[', '·', ']'], Style::fg(Yellow).bold()),
+                StyledCell::new(['›', '◎', '‹'], Style::fg(Magenta).bold()),
+            ],
+            ['«', '»'],
+        )
+    }
+
+    /// Plain ASCII without colors, the same symbols as the text board
+    /// format: `.` water, `o` miss, `x` hit, `#` ship.
+    pub fn ascii() -> Theme {
+        let plain = Style::default();
+        Theme::new(
+            "ascii",
+            [
+                StyledCell::new([' ', '.', ' '], plain),
+                StyledCell::new([' ', 'o', ' '], plain),
+                StyledCell::new([' ', 'x', ' '], plain),
+                StyledCell::new([' ', '#', ' '], plain),
+                StyledCell::new([' ', '@', ' '], plain),
+                StyledCell::new([' ', '!', ' '], plain),
+                StyledCell::new(['[', '+', ']'], plain),
+                StyledCell::new(['>', '!', '<'], plain),
+            ],
+            ['(', ')'],
+        )
+    }
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        THEMES
+            .into_iter()
+            .map(|theme| theme())
+            .find(|theme| theme.name == name)
+    }
+
+    pub fn cell(&self, kind: CellKind) -> StyledCell {
+        let mut cell = self.cells[kind.index()];
+        if let Some(color) = self.palette.color(kind) {
+            cell.style.fg = color;
+        }
+        cell
+    }
+
+    /// The cell of the kind in the highlight frame, styled like an incoming
+    /// shot, so a highlighted cell still shows what is on it.
+    pub fn highlighted(&self, kind: CellKind) -> StyledCell {
+        let [left, right] = self.highlight;
+        let text = self.cell(kind).text;
+        let style = self.cell(CellKind::Incoming).style;
+        StyledCell::new([left, text[1], right], style)
+    }
+
+    pub fn set_cell(&mut self, kind: CellKind, cell: StyledCell) {
+        self.cells[kind.index()] = cell;
+    }
+
+    /// Drops all colors, keeping the characters and bold text.
+    pub fn without_colors(mut self) -> Theme {
+        for cell in self.cells.iter_mut() {
+            cell.style.fg = Color::Default;
+        }
+        self.palette = Palette::Standard;
+        self
+    }
+
+    /// First two cell kinds drawn with the same characters, which could only
+    /// be told apart by color.
+    pub fn ambiguous_cells(&self) -> Option<(CellKind, CellKind)> {
+        CELL_KINDS.iter().enumerate().find_map(|(i, &kind)| {
+            CELL_KINDS[i + 1..]
+                .iter()
+                .find(|&&other| self.cell(other).text == self.cell(kind).text)
+                .map(|&other| (kind, other))
+        })
+    }
+
+    /// Loads the theme file from the configuration directory. A theme
+    /// given on the command line replaces the file, and a palette wins over
+    /// the one in the file. Colors are dropped when the `NO_COLOR`
+    /// environment variable is set.
+    pub fn load(theme: Option<Theme>, palette: Option<Palette>) -> Result<Theme, ConfigError> {
+        let mut theme = match theme {
+            Some(theme) => theme,
+            None => match read_config(THEME_FILE)? {
+                Some(text) => Theme::parse(&text)?,
+                None => Theme::default(),
+            },
+        };
+
+        if let Some(palette) = palette {
+            theme.palette = palette;
+        }
+
+        match env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Ok(theme.without_colors()),
+            _ => Ok(theme),
+        }
+    }
+
+    /// Parses a theme file. `theme = name` picks a builtin theme, and lines
+    /// like `hit = " X " red bold` override single cells of it. Every cell
+    /// kind has to keep its own characters, so the board can be read without
+    /// colors.
+    pub fn parse(text: &str) -> Result<Theme, ConfigError> {
+        let entries = parse_entries(text)?;
+        let mut theme = Theme::default();
+        let mut palette = None;
+
+        for entry in entries.iter().filter(|entry| entry.name == "theme") {
+            theme = Theme::by_name(entry.value).ok_or_else(|| {
+                ConfigError::syntax(entry.line, format!("unknown theme `{}`", entry.value))
+            })?;
+        }
+
+        for entry in entries.iter().filter(|entry| entry.name == "palette") {
+            palette = Some(Palette::by_name(entry.value).ok_or_else(|| {
+                ConfigError::syntax(entry.line, format!("unknown palette `{}`", entry.value))
+            })?);
+        }
+
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.name != "theme" && entry.name != "palette")
+        {
+            let Some(kind) = CELL_KINDS
+                .into_iter()
+                .find(|kind| kind.name() == entry.name)
+            else {
+                return Err(ConfigError::syntax(
+                    entry.line,
+                    format!("unknown cell `{}`", entry.name),
+                ));
+            };
+
+            let cell = parse_cell(entry.value)
+                .map_err(|message| ConfigError::syntax(entry.line, message))?;
+            theme.set_cell(kind, cell);
+        }
+
+        // Checked once every cell is set, so swapping two cells works in
+        // either order
+        if let Some((first, second)) = theme.ambiguous_cells() {
+            let line = entries
+                .iter()
+                .filter(|entry| entry.name == first.name() || entry.name == second.name())
+                .map(|entry| entry.line)
+                .max()
+                .unwrap_or(0);
+            return Err(ConfigError::syntax(
+                line,
+                format!(
+                    "`{}` and `{}` cells look the same",
+                    first.name(),
+                    second.name()
+                ),
+            ));
+        }
+
+        if let Some(palette) = palette {
+            theme.palette = palette;
+        }
+        Ok(theme)
+    }
+}
+
+/// Parses `" X " red bold`: the quoted cell text, then its style.
+fn parse_cell(value: &str) -> Result<StyledCell, String> {
+    let Some(rest) = value.strip_prefix('"') else {
+        return Err(String::from("cell text must be quoted"));
+    };
+    let Some((text, style)) = rest.split_once('"') else {
+        return Err(String::from("missing closing quote"));
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let Ok(text) = <[char; CELL_WIDTH]>::try_from(chars) else {
+        return Err(format!("cell text must be {} characters wide", CELL_WIDTH));
+    };
+
+    let mut parsed = Style::default();
+    for word in style.split_whitespace() {
+        if word == "bold" {
+            parsed.bold = true;
+        } else if let Some(&(_, color)) = COLOR_NAMES.iter().find(|(name, _)| *name == word) {
+            parsed.fg = color;
+        } else if let Ok(index) = word.parse::<u8>() {
+            parsed.fg = Color::Indexed(index);
+        } else {
+            return Err(format!("unknown style `{}`", word));
+        }
+    }
+
+    Ok(StyledCell::new(text, parsed))
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn builtin_themes_by_name() {
+        for theme in THEMES {
+            let theme = theme();
+            assert_eq!(Theme::by_name(theme.name), Some(theme));
+        }
+        assert_eq!(Theme::by_name("neon"), None);
+    }
+
+    #[test]
+    fn theme_file_overrides_cells() {
+        let theme =
+            Theme::parse("theme = unicode\nhit = \" X \" magenta bold\nmiss=\"~~~\"").unwrap();
+        assert_eq!(theme.name, "unicode");
+        assert_eq!(
+            theme.cell(CellKind::Hit),
+            StyledCell::new([' ', 'X', ' '], Style::fg(Color::Magenta).bold())
+        );
+        assert_eq!(theme.cell(CellKind::Miss).text, ['~', '~', '~']);
+        assert_eq!(
+            theme.cell(CellKind::Ship),
+            Theme::unicode().cell(CellKind::Ship)
+        );
+    }
+
+    #[test]
+    fn theme_file_errors() {
+        assert!(Theme::parse("theme = neon").is_err());
+        assert!(Theme::parse("splash = \" ~ \"").is_err());
+        assert!(Theme::parse("hit = \"**\"").is_err());
+        assert!(Theme::parse("hit = \" * \" purple").is_err());
+        assert!(Theme::parse("hit = * red").is_err());
+    }
+
+    #[test]
+    fn sgr_sequences() {
+        assert_eq!(Style::default().sgr(), "\u{001B}[0m");
+        assert_eq!(Style::fg(Color::Red).sgr(), "\u{001B}[0;31m");
+        assert_eq!(Style::fg(Color::White).bold().sgr(), "\u{001B}[0;1;37m");
+        assert_eq!(Style::fg(Color::Indexed(208)).sgr(), "\u{001B}[0;38;5;208m");
+    }
+
+    #[test]
+    fn builtin_themes_distinguish_cells_by_glyph() {
+        for theme in THEMES {
+            assert_eq!(theme().ambiguous_cells(), None);
+        }
+        assert!(Theme::parse("hit = \" ~ \"\nunknown = \" ~ \"").is_err());
+
+        // Swapping two cells only works when both lines are applied
+        let swapped = Theme::parse("hit = \"[~]\"\nunknown = \"[*]\"").unwrap();
+        assert_eq!(swapped.cell(CellKind::Unknown).text, ['[', '*', ']']);
+    }
+
+    #[test]
+    fn colorblind_palette() {
+        let theme = Theme::parse("palette = colorblind\nhit = \"(X)\" red bold").unwrap();
+        assert_eq!(theme.palette, Palette::Colorblind);
+
+        let hit = theme.cell(CellKind::Hit);
+        assert_eq!(hit.text, ['(', 'X', ')']);
+        assert_eq!(hit.style, Style::fg(Color::Indexed(166)).bold());
+
+        let colors: Vec<Color> = CELL_KINDS
+            .into_iter()
+            .map(|kind| theme.cell(kind).style.fg)
+            .collect();
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[i + 1..].contains(color));
+        }
+
+        assert!(Theme::parse("palette = sepia").is_err());
+        assert_eq!(
+            Theme::parse("theme = colorblind").unwrap().palette,
+            Palette::Colorblind
+        );
+        assert_eq!(
+            Theme::parse("theme = colorblind\npalette = standard")
+                .unwrap()
+                .palette,
+            Palette::Standard
+        );
+        assert_eq!(
+            theme.without_colors().cell(CellKind::Hit).style,
+            Style::default().bold()
+        );
+    }
+
+    #[test]
+    fn highlights_keep_the_glyph() {
+        let theme = Theme::classic();
+        let hit = theme.highlighted(CellKind::Hit);
+        assert_eq!(hit.text, ['(', '*', ')']);
+        assert_eq!(hit.style, theme.cell(CellKind::Incoming).style);
+        assert_ne!(hit.text, theme.highlighted(CellKind::Miss).text);
+    }
+
+    #[test]
+    fn builtin_highlights_look_like_no_other_cell() {
+        for theme in THEMES {
+            let theme = theme();
+            for kind in CELL_KINDS {
+                let text = theme.highlighted(kind).text;
+                for other in CELL_KINDS {
+                    assert_ne!(text, theme.cell(other).text, "{} {:?}", theme.name, kind);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_color_keeps_glyphs() {
+        let theme = Theme::classic().without_colors();
+        assert_eq!(theme.cell(CellKind::Hit).style, Style::default());
+        assert_eq!(theme.cell(CellKind::Hit).text, ['[', '*', ']']);
+    }
+}
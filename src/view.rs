@@ -0,0 +1,161 @@
+use crate::board_api::ship_size;
+use crate::constants::BOARD_MASK;
+use crate::game::{Game, Player, SHIPS_COUNT, SHIP_SIZES};
+
+/// What one player is allowed to know about the game. Interfaces and
+/// strategies get this instead of the `Game`, so they cannot see the enemy
+/// fleet by accident.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerView {
+    pub player: Player,
+    /// The player's own fleet, by layer.
+    pub ships: [u128; SHIPS_COUNT],
+    /// Cells of the enemy board the player knows: the shots and the water
+    /// around sunk ships.
+    pub shots: u128,
+    /// Shots that hit an enemy ship.
+    pub hits: u128,
+    /// Enemy shots on the player's board.
+    pub incoming: u128,
+    /// Enemy ships that are sunk, by layer. Ships still afloat are 0.
+    pub sunk_enemy: [u128; SHIPS_COUNT],
+    pub winner: Option<Player>,
+}
+
+/// What a viewer knows about one ship of a fleet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ShipStatus {
+    pub size: usize,
+    /// Hit cells, known for own ships and for sunk enemy ships.
+    pub damage: usize,
+    pub sunk: bool,
+    /// Whether the ship belongs to the viewer, who sees all of it.
+    pub revealed: bool,
+}
+
+impl PlayerView {
+    pub fn new(game: &Game, player: Player) -> PlayerView {
+        let enemy = game.get_board(player.other());
+        let shots = game.get_shoots(player);
+
+        let mut sunk_enemy = [0; SHIPS_COUNT];
+        for (layer, sunk) in sunk_enemy.iter_mut().enumerate() {
+            if game.is_sunk(player.other(), layer) {
+                *sunk = game.get_ships(player.other())[layer];
+            }
+        }
+
+        PlayerView {
+            player,
+            ships: game.get_ships(player),
+            shots,
+            hits: shots & enemy,
+            incoming: game.get_shoots(player.other()),
+            sunk_enemy,
+            winner: game.get_winner(),
+        }
+    }
+
+    /// What the enemy knows, once its fleet is shown at the end of the
+    /// game.
+    pub fn enemy_view(&self, ships: [u128; SHIPS_COUNT]) -> PlayerView {
+        PlayerView {
+            player: self.player.other(),
+            ships,
+            shots: self.incoming,
+            hits: self.incoming & self.board(),
+            incoming: self.shots,
+            sunk_enemy: core::array::from_fn(|layer| match self.is_own_sunk(layer) {
+                true => self.ships[layer],
+                false => 0,
+            }),
+            winner: self.winner,
+        }
+    }
+
+    /// All of the player's ships on one board.
+    pub fn board(&self) -> u128 {
+        self.ships.iter().fold(0, |board, ship| board | ship)
+    }
+
+    pub fn is_own_sunk(&self, layer: usize) -> bool {
+        let ship = self.ships[layer];
+        ship != 0 && ship & !self.incoming == 0
+    }
+
+    pub fn is_enemy_sunk(&self, layer: usize) -> bool {
+        self.sunk_enemy[layer] != 0
+    }
+
+    /// Enemy cells the player knows nothing about yet.
+    pub fn unknown(&self) -> u128 {
+        BOARD_MASK & !self.shots
+    }
+
+    /// Hits on enemy ships that are not sunk yet, the cells worth shooting
+    /// next to.
+    pub fn open_hits(&self) -> u128 {
+        let sunk = self.sunk_enemy.iter().fold(0, |board, ship| board | ship);
+        self.hits & !sunk
+    }
+
+    pub fn own_fleet(&self) -> [ShipStatus; SHIPS_COUNT] {
+        core::array::from_fn(|layer| ShipStatus {
+            size: SHIP_SIZES[layer],
+            damage: ship_size(self.ships[layer] & self.incoming),
+            sunk: self.is_own_sunk(layer),
+            revealed: true,
+        })
+    }
+
+    pub fn enemy_fleet(&self) -> [ShipStatus; SHIPS_COUNT] {
+        core::array::from_fn(|layer| {
+            let sunk = self.is_enemy_sunk(layer);
+            ShipStatus {
+                size: SHIP_SIZES[layer],
+                damage: if sunk { SHIP_SIZES[layer] } else { 0 },
+                sunk,
+                revealed: false,
+            }
+        })
+    }
+}
+
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    #[allow(unused_imports)]
+    use crate::{coord::Coord, rng::Rng};
+
+    #[test]
+    fn view_hides_enemy_fleet() {
+        let mut game = Game::default();
+        game.place_random_fleet(Player::Alpha, &mut Rng::new(4));
+        game.place_random_fleet(Player::Beta, &mut Rng::new(9));
+
+        let boat = game.get_ships(Player::Beta)[4];
+        let carrier = Coord::from_board(game.get_ships(Player::Beta)[0]).unwrap();
+        for coord in Coord::all().filter(|coord| boat & coord.to_board() != 0) {
+            game.shoot(Player::Alpha, coord.to_board()).unwrap();
+        }
+        game.shoot(Player::Alpha, carrier.to_board()).unwrap();
+
+        let view = PlayerView::new(&game, Player::Alpha);
+        assert_eq!(view.ships, game.ships_alpha);
+        assert_eq!(view.hits, boat | carrier.to_board());
+        assert_eq!(view.sunk_enemy, [0, 0, 0, 0, boat]);
+        assert_eq!(view.open_hits(), carrier.to_board());
+        assert_eq!(view.unknown() & view.shots, 0);
+        assert_eq!(view.enemy_fleet()[0].damage, 0);
+        assert_eq!(view.enemy_fleet()[4].damage, 2);
+
+        let view = PlayerView::new(&game, Player::Beta);
+        assert_eq!(view.incoming, game.shoots_alpha);
+        assert_eq!(view.own_fleet()[0].damage, 1);
+        assert!(view.is_own_sunk(4));
+        assert_eq!(view.hits, 0);
+
+        let enemy = view.enemy_view(game.ships_alpha);
+        assert_eq!(enemy, PlayerView::new(&game, Player::Alpha));
+    }
+}